@@ -0,0 +1,794 @@
+use crate::diagnostics::Diagnostic;
+use crate::is_iteration;
+use crate::scope::BindingKind;
+use boa_ast::declaration::{Binding, LexicalDeclaration, VarDeclaration, Variable};
+use boa_ast::expression::literal::Literal;
+use boa_ast::expression::operator::assign::AssignTarget;
+use boa_ast::expression::operator::unary::UnaryOp;
+use boa_ast::expression::operator::update::UpdateTarget;
+use boa_ast::expression::operator::{Assign, Unary, Update};
+use boa_ast::expression::Identifier;
+use boa_ast::function::{
+    ArrowFunction, AsyncArrowFunction, AsyncFunctionDeclaration, AsyncFunctionExpression,
+    AsyncGeneratorDeclaration, AsyncGeneratorExpression, ClassDeclaration, ClassElement,
+    ClassExpression, FormalParameterList, FunctionDeclaration, FunctionExpression,
+    GeneratorDeclaration, GeneratorExpression,
+};
+use boa_ast::statement::iteration::{
+    DoWhileLoop, ForInLoop, ForLoop, ForLoopInitializer, ForOfLoop, WhileLoop,
+};
+use boa_ast::statement::{Block, Break, Continue, Labelled, LabelledItem, Switch};
+use boa_ast::visitor::{VisitWith, Visitor};
+use boa_ast::{Declaration, Expression, Statement, StatementListItem};
+use boa_interner::{Interner, Sym};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::ops::ControlFlow;
+
+#[derive(Default)]
+struct Scope {
+    bindings: HashMap<Sym, BindingKind>,
+    function: bool,
+}
+
+#[derive(PartialEq, Eq)]
+enum TargetKind {
+    Loop,
+    Switch,
+    Labelled,
+}
+
+struct JumpTarget {
+    labels: Vec<Sym>,
+    kind: TargetKind,
+}
+
+// Reports early errors, and errors codegen could only catch partially, before
+// any code is generated. Unlike codegen it sees through function boundaries,
+// so assigning to a constant from a nested function is caught too.
+pub struct EarlyErrors<'a> {
+    interner: &'a Interner,
+    strict: bool,
+    scopes: Vec<Scope>,
+    jump_targets: Vec<JumpTarget>,
+    pending_labels: Vec<Sym>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> EarlyErrors<'a> {
+    pub fn new(interner: &'a Interner, strict: bool) -> Self {
+        Self {
+            interner,
+            strict,
+            scopes: vec![Scope {
+                function: true,
+                ..Scope::default()
+            }],
+            jump_targets: vec![],
+            pending_labels: vec![],
+            diagnostics: vec![],
+        }
+    }
+
+    pub fn check<'ast>(
+        &mut self,
+        items: impl IntoIterator<Item = &'ast StatementListItem> + Clone,
+    ) {
+        self.declare_lexical_names(items.clone());
+
+        for item in items {
+            let _ = self.visit_statement_list_item(item);
+        }
+    }
+
+    fn name(&self, name: Sym) -> &str {
+        self.interner.resolve_expect(name).utf8().unwrap()
+    }
+
+    fn error(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic::error(message));
+    }
+
+    // `let` and `const` are in scope for their whole block, so they're
+    // declared up front and later references resolve to them.
+    fn declare_lexical_names<'ast>(
+        &mut self,
+        items: impl IntoIterator<Item = &'ast StatementListItem>,
+    ) {
+        for item in items {
+            if let StatementListItem::Declaration(Declaration::Lexical(declaration)) = item {
+                self.declare_lexical_declaration(declaration);
+            }
+        }
+    }
+
+    fn declare_lexical_declaration(&mut self, declaration: &LexicalDeclaration) {
+        let kind = match declaration {
+            LexicalDeclaration::Let(_) => BindingKind::Let,
+            LexicalDeclaration::Const(_) => BindingKind::Const,
+        };
+        let variables: &[Variable] = declaration.variable_list().as_ref();
+
+        for variable in variables {
+            if let Binding::Identifier(identifier) = variable.binding() {
+                self.declare(identifier.sym(), kind);
+            }
+        }
+    }
+
+    fn declare(&mut self, name: Sym, kind: BindingKind) {
+        if kind != BindingKind::Var {
+            let scope = self.scopes.last_mut().unwrap();
+
+            if scope.bindings.insert(name, kind).is_some() {
+                self.error(format!(
+                    "Identifier `{}` has already been declared",
+                    self.name(name)
+                ));
+            }
+
+            return;
+        }
+
+        let function = self.function_scope();
+        let conflicts = self.scopes[function..].iter().any(|scope| {
+            scope
+                .bindings
+                .get(&name)
+                .is_some_and(|existing| *existing != BindingKind::Var)
+        });
+
+        if conflicts {
+            self.error(format!(
+                "Cannot redeclare block-scoped variable `{}` with var",
+                self.name(name)
+            ));
+        } else {
+            self.scopes[function].bindings.insert(name, kind);
+        }
+    }
+
+    fn function_scope(&self) -> usize {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.function)
+            .unwrap_or_default()
+    }
+
+    fn check_assignment(&mut self, name: Sym) {
+        let kind = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.bindings.get(&name));
+
+        if kind == Some(&BindingKind::Const) {
+            self.error(format!(
+                "Cannot assign to `{}` because it is a constant",
+                self.name(name)
+            ));
+        }
+    }
+
+    // Every kind of function gets its own scope, and labels and loops outside
+    // of it can't be jumped to from its body. Arrow functions and methods
+    // never allow duplicate parameters, other functions only do in sloppy
+    // mode with a simple parameter list.
+    fn check_function(
+        &mut self,
+        parameters: &FormalParameterList,
+        body: &[StatementListItem],
+        unique_parameters: bool,
+    ) {
+        self.scopes.push(Scope {
+            function: true,
+            ..Scope::default()
+        });
+        let jump_targets = std::mem::take(&mut self.jump_targets);
+        let pending_labels = std::mem::take(&mut self.pending_labels);
+        let strict = self.strict;
+        self.strict |= has_use_strict_directive(body, self.interner);
+
+        let unique_parameters = unique_parameters || self.strict || !parameters.is_simple();
+        let mut parameter_names = HashSet::new();
+        for parameter in parameters.as_ref() {
+            if let Binding::Identifier(identifier) = parameter.variable().binding() {
+                if !parameter_names.insert(identifier.sym()) && unique_parameters {
+                    self.error(format!(
+                        "Parameter `{}` has already been declared",
+                        self.name(identifier.sym())
+                    ));
+                }
+                self.declare(identifier.sym(), BindingKind::Var);
+            }
+            if let Some(init) = parameter.variable().init() {
+                let _ = self.visit_expression(init);
+            }
+        }
+
+        // `var`s are hoisted, so they shadow outer bindings even in code
+        // that comes before the declaration. Conflicts with `let` and `const`
+        // are reported once the declaration itself is visited.
+        self.declare_lexical_names(body);
+        for name in var_declared_names(body) {
+            let scope = self.scopes.last_mut().unwrap();
+            scope.bindings.entry(name.sym()).or_insert(BindingKind::Var);
+        }
+
+        for item in body {
+            let _ = self.visit_statement_list_item(item);
+        }
+
+        self.strict = strict;
+        self.pending_labels = pending_labels;
+        self.jump_targets = jump_targets;
+        self.scopes.pop();
+    }
+
+    fn check_loop(&mut self, visit: impl FnOnce(&mut Self)) {
+        let labels = std::mem::take(&mut self.pending_labels);
+
+        self.jump_targets.push(JumpTarget {
+            labels,
+            kind: TargetKind::Loop,
+        });
+        visit(self);
+        self.jump_targets.pop();
+    }
+}
+
+impl<'ast> Visitor<'ast> for EarlyErrors<'_> {
+    type BreakTy = Infallible;
+
+    fn visit_block(&mut self, block: &'ast Block) -> ControlFlow<Self::BreakTy> {
+        self.scopes.push(Scope::default());
+        self.check(block.statement_list().statements());
+        self.scopes.pop();
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_var_declaration(
+        &mut self,
+        declaration: &'ast VarDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        let variables: &[Variable] = declaration.0.as_ref();
+
+        for variable in variables {
+            if let Binding::Identifier(identifier) = variable.binding() {
+                self.declare(identifier.sym(), BindingKind::Var);
+            }
+        }
+
+        declaration.visit_with(self)
+    }
+
+    fn visit_function_declaration(
+        &mut self,
+        function: &'ast FunctionDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), false);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_function_expression(
+        &mut self,
+        function: &'ast FunctionExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), false);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_arrow_function(
+        &mut self,
+        function: &'ast ArrowFunction,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), true);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_arrow_function(
+        &mut self,
+        function: &'ast AsyncArrowFunction,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), true);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_function_declaration(
+        &mut self,
+        function: &'ast AsyncFunctionDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), false);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_function_expression(
+        &mut self,
+        function: &'ast AsyncFunctionExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), false);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_generator_declaration(
+        &mut self,
+        function: &'ast GeneratorDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), false);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_generator_expression(
+        &mut self,
+        function: &'ast GeneratorExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), false);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_generator_declaration(
+        &mut self,
+        function: &'ast AsyncGeneratorDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), false);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_generator_expression(
+        &mut self,
+        function: &'ast AsyncGeneratorExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_function(function.parameters(), function.body().statements(), false);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_class_element(&mut self, element: &'ast ClassElement) -> ControlFlow<Self::BreakTy> {
+        match element {
+            ClassElement::MethodDefinition(method) => {
+                self.check_function(method.parameters(), method.body().statements(), true);
+
+                ControlFlow::Continue(())
+            }
+            element => element.visit_with(self),
+        }
+    }
+
+    fn visit_for_loop(&mut self, for_loop: &'ast ForLoop) -> ControlFlow<Self::BreakTy> {
+        self.scopes.push(Scope::default());
+        if let Some(ForLoopInitializer::Lexical(declaration)) = for_loop.init() {
+            self.declare_lexical_declaration(declaration);
+        }
+
+        self.check_loop(|this| {
+            let _ = for_loop.visit_with(this);
+        });
+        self.scopes.pop();
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_while_loop(&mut self, while_loop: &'ast WhileLoop) -> ControlFlow<Self::BreakTy> {
+        self.check_loop(|this| {
+            let _ = while_loop.visit_with(this);
+        });
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_do_while_loop(
+        &mut self,
+        do_while_loop: &'ast DoWhileLoop,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.check_loop(|this| {
+            let _ = do_while_loop.visit_with(this);
+        });
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_for_in_loop(&mut self, for_in_loop: &'ast ForInLoop) -> ControlFlow<Self::BreakTy> {
+        self.check_loop(|this| {
+            let _ = for_in_loop.visit_with(this);
+        });
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_for_of_loop(&mut self, for_of_loop: &'ast ForOfLoop) -> ControlFlow<Self::BreakTy> {
+        self.check_loop(|this| {
+            let _ = for_of_loop.visit_with(this);
+        });
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_switch(&mut self, switch: &'ast Switch) -> ControlFlow<Self::BreakTy> {
+        self.scopes.push(Scope::default());
+        self.declare_lexical_names(
+            switch
+                .cases()
+                .iter()
+                .flat_map(|case| case.body().statements()),
+        );
+        self.jump_targets.push(JumpTarget {
+            labels: vec![],
+            kind: TargetKind::Switch,
+        });
+
+        let _ = switch.visit_with(self);
+
+        self.jump_targets.pop();
+        self.scopes.pop();
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_labelled(&mut self, labelled: &'ast Labelled) -> ControlFlow<Self::BreakTy> {
+        match labelled.item() {
+            LabelledItem::Statement(statement) if is_iteration(statement) => {
+                self.pending_labels.push(labelled.label());
+                let _ = self.visit_statement(statement);
+            }
+            item => {
+                self.jump_targets.push(JumpTarget {
+                    labels: vec![labelled.label()],
+                    kind: TargetKind::Labelled,
+                });
+                let _ = self.visit_labelled_item(item);
+                self.jump_targets.pop();
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_break(&mut self, node: &'ast Break) -> ControlFlow<Self::BreakTy> {
+        let found = self.jump_targets.iter().any(|target| match node.label() {
+            Some(label) => target.labels.contains(&label),
+            None => target.kind != TargetKind::Labelled,
+        });
+
+        if !found {
+            match node.label() {
+                Some(label) => self.error(format!(
+                    "Undefined label `{}` in break statement",
+                    self.name(label)
+                )),
+                None => self.error("Illegal break statement outside of a loop".to_string()),
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_continue(&mut self, node: &'ast Continue) -> ControlFlow<Self::BreakTy> {
+        let found = self.jump_targets.iter().any(|target| {
+            target.kind == TargetKind::Loop
+                && match node.label() {
+                    Some(label) => target.labels.contains(&label),
+                    None => true,
+                }
+        });
+
+        if !found {
+            match node.label() {
+                Some(label) => self.error(format!(
+                    "Undefined label `{}` in continue statement",
+                    self.name(label)
+                )),
+                None => self.error("Illegal continue statement outside of a loop".to_string()),
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_assign(&mut self, assign: &'ast Assign) -> ControlFlow<Self::BreakTy> {
+        if let AssignTarget::Identifier(identifier) = assign.lhs() {
+            self.check_assignment(identifier.sym());
+        }
+
+        assign.visit_with(self)
+    }
+
+    fn visit_update(&mut self, update: &'ast Update) -> ControlFlow<Self::BreakTy> {
+        if let UpdateTarget::Identifier(identifier) = update.target() {
+            self.check_assignment(identifier.sym());
+        }
+
+        update.visit_with(self)
+    }
+
+    fn visit_unary(&mut self, unary: &'ast Unary) -> ControlFlow<Self::BreakTy> {
+        if self.strict
+            && unary.op() == UnaryOp::Delete
+            && matches!(unary.target(), Expression::Identifier(_))
+        {
+            self.error("Cannot delete a variable in strict mode".to_string());
+        }
+
+        unary.visit_with(self)
+    }
+}
+
+// Collects the `var` declarations in a function body, without looking into
+// nested functions, which hoist their own.
+#[derive(Default)]
+struct VarDeclarations<'ast>(Vec<&'ast Variable>);
+
+impl<'ast> Visitor<'ast> for VarDeclarations<'ast> {
+    type BreakTy = Infallible;
+
+    fn visit_var_declaration(
+        &mut self,
+        declaration: &'ast VarDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        let variables: &'ast [Variable] = declaration.0.as_ref();
+        self.0.extend(variables);
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_function_declaration(
+        &mut self,
+        _: &'ast FunctionDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_function_expression(
+        &mut self,
+        _: &'ast FunctionExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_arrow_function(&mut self, _: &'ast ArrowFunction) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_arrow_function(
+        &mut self,
+        _: &'ast AsyncArrowFunction,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_function_declaration(
+        &mut self,
+        _: &'ast AsyncFunctionDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_function_expression(
+        &mut self,
+        _: &'ast AsyncFunctionExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_generator_declaration(
+        &mut self,
+        _: &'ast GeneratorDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_generator_expression(
+        &mut self,
+        _: &'ast GeneratorExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_generator_declaration(
+        &mut self,
+        _: &'ast AsyncGeneratorDeclaration,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_async_generator_expression(
+        &mut self,
+        _: &'ast AsyncGeneratorExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_class_declaration(&mut self, _: &'ast ClassDeclaration) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_class_expression(&mut self, _: &'ast ClassExpression) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+}
+
+// The `var` declarations in a function body or script, in source order.
+pub fn var_declarations<'ast>(
+    items: impl IntoIterator<Item = &'ast StatementListItem>,
+) -> Vec<&'ast Variable> {
+    let mut declarations = VarDeclarations::default();
+
+    for item in items {
+        let _ = declarations.visit_statement_list_item(item);
+    }
+
+    declarations.0
+}
+
+// The identifiers `var` declarations in a function body or script bind, in
+// source order and possibly repeated.
+pub fn var_declared_names<'ast>(
+    items: impl IntoIterator<Item = &'ast StatementListItem>,
+) -> Vec<Identifier> {
+    var_declarations(items)
+        .into_iter()
+        .filter_map(|variable| match variable.binding() {
+            Binding::Identifier(identifier) => Some(*identifier),
+            Binding::Pattern(_) => None,
+        })
+        .collect()
+}
+
+// Scripts opt into strict mode with a leading "use strict" directive.
+pub fn has_use_strict_directive(items: &[StatementListItem], interner: &Interner) -> bool {
+    items
+        .iter()
+        .map_while(|item| match item {
+            StatementListItem::Statement(Statement::Expression(Expression::Literal(
+                Literal::String(directive),
+            ))) => Some(*directive),
+            _ => None,
+        })
+        .any(|directive| interner.resolve_expect(directive).utf8() == Some("use strict"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boa_parser::{Parser, Source};
+
+    fn messages<'ast>(
+        interner: &Interner,
+        items: impl IntoIterator<Item = &'ast StatementListItem> + Clone,
+        strict: bool,
+    ) -> Vec<String> {
+        let mut checker = EarlyErrors::new(interner, strict);
+        checker.check(items);
+
+        checker
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    // Each source is parsed on its own and the scripts are checked as one
+    // statement list, so errors boa's parser would catch still get here.
+    fn check(sources: &[&str], strict: bool) -> Vec<String> {
+        let mut interner = Interner::new();
+        let scripts = sources
+            .iter()
+            .map(|source| {
+                Parser::new(Source::from_bytes(source))
+                    .parse_script(&mut interner)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        messages(
+            &interner,
+            scripts.iter().flat_map(|script| script.statements().iter()),
+            strict,
+        )
+    }
+
+    #[test]
+    fn rejects_const_reassignment() {
+        assert_eq!(
+            check(&["const a = 1; a = 2;"], false),
+            ["Cannot assign to `a` because it is a constant"]
+        );
+        assert_eq!(
+            check(&["const a = 1; function f() { a++; }"], false),
+            ["Cannot assign to `a` because it is a constant"]
+        );
+        assert!(check(&["const a = 1; function f(a) { a = 2; }"], false).is_empty());
+    }
+
+    #[test]
+    fn rejects_duplicate_lexical_declarations() {
+        assert_eq!(
+            check(&["let a = 1;", "const a = 2;"], false),
+            ["Identifier `a` has already been declared"]
+        );
+        assert_eq!(
+            check(&["let a;", "var a;"], false),
+            ["Cannot redeclare block-scoped variable `a` with var"]
+        );
+        assert!(check(&["let a;", "{ let a; }"], false).is_empty());
+    }
+
+    #[test]
+    fn rejects_deleting_identifiers_in_strict_mode() {
+        assert_eq!(
+            check(&["delete x;"], true),
+            ["Cannot delete a variable in strict mode"]
+        );
+        assert!(check(&["delete x;"], false).is_empty());
+        assert_eq!(
+            check(&["function f() { 'use strict'; delete x; }"], false),
+            ["Cannot delete a variable in strict mode"]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_parameters_in_strict_mode() {
+        assert_eq!(
+            check(&["function f(a, a) {}"], true),
+            ["Parameter `a` has already been declared"]
+        );
+        assert!(check(&["function f(a, a) {}"], false).is_empty());
+
+        // Arrow functions, non-simple parameter lists and functions that opt
+        // into strict mode themselves are rejected by boa's parser already.
+        for source in [
+            "(a, a) => a;",
+            "function f(a, a = 1) {}",
+            "function f(a, a) { 'use strict'; }",
+        ] {
+            let mut interner = Interner::new();
+            let result = Parser::new(Source::from_bytes(source)).parse_script(&mut interner);
+            assert!(result.is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn rejects_undefined_jump_targets() {
+        // Jumps to labels that don't resolve can't be parsed, so they are
+        // built around the label of a parsed loop.
+        let mut interner = Interner::new();
+        let script = Parser::new(Source::from_bytes("outer: while (true) {}"))
+            .parse_script(&mut interner)
+            .unwrap();
+        let StatementListItem::Statement(Statement::Labelled(labelled)) = &script.statements()[0]
+        else {
+            unreachable!()
+        };
+        let label = labelled.label();
+        let jumps = [
+            StatementListItem::Statement(Statement::Break(Break::new(Some(label)))),
+            StatementListItem::Statement(Statement::Continue(Continue::new(Some(label)))),
+            StatementListItem::Statement(Statement::Break(Break::new(None))),
+        ];
+
+        assert_eq!(
+            messages(&interner, script.statements().iter().chain(&jumps), false),
+            [
+                "Undefined label `outer` in break statement",
+                "Undefined label `outer` in continue statement",
+                "Illegal break statement outside of a loop",
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_jumps_to_enclosing_loops() {
+        let source = "outer: while (true) { while (true) { continue outer; } break outer; }";
+
+        assert!(check(&[source], false).is_empty());
+    }
+}
@@ -0,0 +1,382 @@
+use jscc::link::{LinkOptions, LinkerFlavor, OutputKind};
+use jscc::pragma::mask_comments_and_literals;
+use jscc::tsconfig::TsConfig;
+use jscc::UnsupportedPolicy;
+use std::path::{Path, PathBuf};
+
+pub enum Command {
+    Run,
+    Build,
+}
+
+#[derive(PartialEq, Eq)]
+pub enum Emit {
+    Link,
+    Object,
+    LlvmIr,
+    Bitcode,
+    Assembly,
+    RustBindings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Auto,
+    Script,
+    Module,
+}
+
+impl InputKind {
+    pub fn resolve(self, input: &Path, source: &[u8], tsconfig: Option<&TsConfig>) -> InputKind {
+        if self != InputKind::Auto {
+            return self;
+        }
+
+        match input.extension().and_then(|extension| extension.to_str()) {
+            Some("mjs" | "mts") => InputKind::Module,
+            Some("cjs" | "cts") => InputKind::Script,
+            // `.ts` files follow the project's module setting, and are ES
+            // modules unless the project compiles to CommonJS.
+            Some("ts") if tsconfig.is_some_and(TsConfig::is_commonjs) => InputKind::Script,
+            Some("ts") => InputKind::Module,
+            // Plain `.js` files can be either, so treat anything with a
+            // top-level import or export statement as a module. Comments and
+            // literals are masked so only code can look like one.
+            _ => {
+                let code = mask_comments_and_literals(&String::from_utf8_lossy(source));
+                let is_module = code.lines().any(|line| {
+                    let line = line.trim_start();
+                    // `import(...)` is a dynamic import, which scripts can use
+                    // too.
+                    let is_import = line.strip_prefix("import").is_some_and(|rest| {
+                        rest.starts_with(|c: char| c.is_whitespace() || c == '{' || c == '*')
+                            && !rest.trim_start().starts_with('(')
+                    });
+
+                    is_import
+                        || line.starts_with("export ")
+                        || line.starts_with("export{")
+                        || line.starts_with("export*")
+                });
+
+                if is_module {
+                    InputKind::Module
+                } else {
+                    InputKind::Script
+                }
+            }
+        }
+    }
+}
+
+pub fn is_typescript(input: &Path) -> bool {
+    matches!(
+        input.extension().and_then(|extension| extension.to_str()),
+        Some("ts" | "mts" | "cts")
+    )
+}
+
+pub struct Options {
+    pub command: Command,
+    pub input: PathBuf,
+    pub input_kind: InputKind,
+    pub output: Option<PathBuf>,
+    pub out_dir: Option<PathBuf>,
+    pub output_kind: OutputKind,
+    pub emit: Vec<Emit>,
+    pub target: Option<String>,
+    pub min_os_version: Option<String>,
+    pub static_link: bool,
+    pub no_pic: bool,
+    pub verify_each: bool,
+    pub opt_level: u8,
+    pub no_main: bool,
+    pub max_errors: Option<usize>,
+    pub unsupported: UnsupportedPolicy,
+    pub annex_b: bool,
+    pub trace_codegen: Option<String>,
+    pub trace_json: bool,
+    pub link: LinkOptions,
+}
+
+pub const USAGE: &str = "usage: jscc <run|build> <input> [--input-kind <script|module|auto>] \
+[-o <output>] [--out-dir <dir>] [--output-kind <exe|staticlib>] \
+[--emit <link|obj|llvm-ir|llvm-bc|asm|rust-bindings>,...] [--target <triple>] \
+[--min-os-version <version>] [--static] [--no-pic] [--framework <name>] [--rpath <path>] \
+[--linker-flavor <cc|clang|lld>] [--link-arg <arg>] [-Wl,<args>] [--no-main] \
+[--max-errors <count>] [--unsupported <error|warn|trap>] [--annex-b] [--opt-level <0|1>] \
+[--verify-each] [--trace-codegen[=<filter>]] [--trace-format <human|json>]";
+
+impl Options {
+    // Libraries never define `main`; their top-level code runs from an
+    // exported init function instead.
+    pub fn has_main(&self) -> bool {
+        !self.no_main && self.output_kind == OutputKind::Executable
+    }
+
+    // Linking is what `build` does unless only intermediate artifacts were
+    // requested.
+    pub fn should_link(&self) -> bool {
+        self.emit.contains(&Emit::Link)
+            || !self.emit.iter().any(|emit| {
+                matches!(
+                    emit,
+                    Emit::Object | Emit::LlvmIr | Emit::Bitcode | Emit::Assembly
+                )
+            })
+    }
+
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = match args.next().as_deref() {
+            Some("run") => Command::Run,
+            Some("build") => Command::Build,
+            Some(command) => return Err(format!("Unknown command {}\n{}", command, USAGE)),
+            None => return Err(USAGE.to_string()),
+        };
+
+        let mut input = None;
+        let mut input_kind = InputKind::Auto;
+        let mut output = None;
+        let mut out_dir = None;
+        let mut output_kind = OutputKind::default();
+        let mut emit = vec![];
+        let mut target = None;
+        let mut min_os_version = None;
+        let mut static_link = false;
+        let mut no_pic = false;
+        let mut verify_each = false;
+        let mut opt_level = 0;
+        let mut no_main = false;
+        let mut max_errors = None;
+        let mut unsupported = UnsupportedPolicy::default();
+        let mut annex_b = false;
+        let mut trace_codegen = None;
+        let mut trace_json = false;
+        let mut link = LinkOptions::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+
+            match arg.as_str() {
+                "--input-kind" => {
+                    input_kind = parse_input_kind(&value(&arg)?)?;
+                }
+                _ if arg.starts_with("--input-kind=") => {
+                    input_kind = parse_input_kind(&arg["--input-kind=".len()..])?;
+                }
+                "-o" | "--output" => output = Some(PathBuf::from(value(&arg)?)),
+                "--out-dir" => out_dir = Some(PathBuf::from(value(&arg)?)),
+                "--output-kind" => {
+                    output_kind = match value(&arg)?.as_str() {
+                        "exe" => OutputKind::Executable,
+                        "staticlib" => OutputKind::StaticLib,
+                        kind => return Err(format!("Unknown output kind {}", kind)),
+                    }
+                }
+                "--emit" => {
+                    for kind in value(&arg)?.split(',') {
+                        emit.push(match kind {
+                            "link" => Emit::Link,
+                            "obj" => Emit::Object,
+                            "llvm-ir" => Emit::LlvmIr,
+                            "llvm-bc" => Emit::Bitcode,
+                            "asm" => Emit::Assembly,
+                            "rust-bindings" => Emit::RustBindings,
+                            kind => return Err(format!("Unknown emit kind {}", kind)),
+                        });
+                    }
+                }
+                "--target" => target = Some(value(&arg)?),
+                "--min-os-version" => min_os_version = Some(value(&arg)?),
+                "--static" => static_link = true,
+                "--no-pic" => no_pic = true,
+                "--verify-each" => verify_each = true,
+                "--opt-level" => {
+                    opt_level = match value(&arg)?.as_str() {
+                        "0" => 0,
+                        "1" => 1,
+                        level => return Err(format!("Unknown optimization level {}", level)),
+                    }
+                }
+                "--annex-b" => annex_b = true,
+                "--no-main" => no_main = true,
+                "--max-errors" => {
+                    let count = value(&arg)?;
+                    max_errors = Some(
+                        count
+                            .parse()
+                            .map_err(|_| format!("Invalid error count {}", count))?,
+                    );
+                }
+                "--unsupported" => {
+                    unsupported = parse_unsupported_policy(&value(&arg)?)?;
+                }
+                _ if arg.starts_with("--unsupported=") => {
+                    unsupported = parse_unsupported_policy(&arg["--unsupported=".len()..])?;
+                }
+                "--trace-codegen" => trace_codegen = Some("jscc=trace".to_string()),
+                _ if arg.starts_with("--trace-codegen=") => {
+                    trace_codegen = Some(arg["--trace-codegen=".len()..].to_string())
+                }
+                "--trace-format" => {
+                    trace_json = match value(&arg)?.as_str() {
+                        "human" => false,
+                        "json" => true,
+                        format => return Err(format!("Unknown trace format {}", format)),
+                    }
+                }
+                "--framework" => link.frameworks.push(value(&arg)?),
+                "--rpath" => link.rpaths.push(value(&arg)?),
+                "--linker-flavor" => {
+                    link.flavor = match value(&arg)?.as_str() {
+                        "cc" => LinkerFlavor::Cc,
+                        "clang" => LinkerFlavor::Clang,
+                        "lld" => LinkerFlavor::Lld,
+                        flavor => return Err(format!("Unknown linker flavor {}", flavor)),
+                    }
+                }
+                "--link-arg" => link.args.push(value(&arg)?),
+                _ if arg.starts_with("-Wl,") => link.args.push(arg),
+                _ if arg.starts_with('-') => {
+                    return Err(format!("Unknown option {}\n{}", arg, USAGE))
+                }
+                _ if input.is_none() => input = Some(PathBuf::from(arg)),
+                _ => return Err(format!("Unexpected argument {}\n{}", arg, USAGE)),
+            }
+        }
+
+        if emit.contains(&Emit::RustBindings) && output_kind != OutputKind::StaticLib && !no_main {
+            return Err(
+                "--emit rust-bindings requires --output-kind staticlib or --no-main".to_string(),
+            );
+        }
+
+        let options = Options {
+            command,
+            input: input.ok_or_else(|| USAGE.to_string())?,
+            input_kind,
+            output,
+            out_dir,
+            output_kind,
+            emit,
+            target,
+            min_os_version,
+            static_link,
+            no_pic,
+            verify_each,
+            opt_level,
+            no_main,
+            max_errors,
+            unsupported,
+            annex_b,
+            trace_codegen,
+            trace_json,
+            link,
+        };
+
+        // An executable can't be linked without `main`.
+        if matches!(options.command, Command::Build)
+            && options.no_main
+            && options.output_kind == OutputKind::Executable
+            && options.should_link()
+        {
+            return Err(
+                "--no-main requires --output-kind staticlib or an --emit list without link"
+                    .to_string(),
+            );
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_input_kind(kind: &str) -> Result<InputKind, String> {
+    match kind {
+        "auto" => Ok(InputKind::Auto),
+        "script" => Ok(InputKind::Script),
+        "module" => Ok(InputKind::Module),
+        kind => Err(format!("Unknown input kind {}", kind)),
+    }
+}
+
+fn parse_unsupported_policy(policy: &str) -> Result<UnsupportedPolicy, String> {
+    match policy {
+        "error" => Ok(UnsupportedPolicy::Error),
+        "warn" => Ok(UnsupportedPolicy::Warn),
+        "trap" => Ok(UnsupportedPolicy::Trap),
+        policy => Err(format!("Unknown unsupported-feature policy {}", policy)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(source: &str) -> InputKind {
+        InputKind::Auto.resolve(Path::new("main.js"), source.as_bytes(), None)
+    }
+
+    #[test]
+    fn detects_modules_by_their_imports_and_exports() {
+        assert_eq!(resolve("import { a } from './a.js';\n"), InputKind::Module);
+        assert_eq!(resolve("import * as a from './a.js';\n"), InputKind::Module);
+        assert_eq!(resolve("export function f() {}\n"), InputKind::Module);
+        assert_eq!(resolve("export{ f };\n"), InputKind::Module);
+        assert_eq!(resolve("let a = 1;\n"), InputKind::Script);
+    }
+
+    #[test]
+    fn treats_dynamic_imports_as_scripts() {
+        assert_eq!(resolve("import('./a.js');\n"), InputKind::Script);
+        assert_eq!(resolve("import ('./a.js');\n"), InputKind::Script);
+    }
+
+    #[test]
+    fn ignores_imports_and_exports_outside_of_code() {
+        assert_eq!(resolve("// export function f() {}\n"), InputKind::Script);
+        assert_eq!(
+            resolve("/*\nexport function f() {}\n*/\n"),
+            InputKind::Script
+        );
+        assert_eq!(
+            resolve("let s = `\nexport function f() {}\n`;\n"),
+            InputKind::Script
+        );
+    }
+
+    #[test]
+    fn lexes_regular_expressions() {
+        // Read as a template literal, the backtick would hide the export.
+        assert_eq!(
+            resolve("let tick = /`/;\nexport const a = 1;\n"),
+            InputKind::Module
+        );
+        // Read as a regular expression, the division would open a template
+        // literal that hides the export.
+        assert_eq!(
+            resolve("let half = a / 2, s = '/`';\nexport const a = 1;\n"),
+            InputKind::Module
+        );
+    }
+
+    #[test]
+    fn resolves_by_extension_first() {
+        let source = b"export const a = 1;\n";
+
+        assert_eq!(
+            InputKind::Auto.resolve(Path::new("main.cjs"), source, None),
+            InputKind::Script
+        );
+        assert_eq!(
+            InputKind::Auto.resolve(Path::new("main.mjs"), b"", None),
+            InputKind::Module
+        );
+        assert_eq!(
+            InputKind::Script.resolve(Path::new("main.mjs"), source, None),
+            InputKind::Script
+        );
+    }
+}
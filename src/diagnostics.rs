@@ -0,0 +1,228 @@
+use boa_ast::Position;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub position: Option<Position>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            position: None,
+            notes: vec![],
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            position: None,
+            notes: vec![],
+        }
+    }
+
+    pub fn with_position(mut self, position: Position) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn from_parse_error(error: &boa_parser::Error) -> Self {
+        use boa_parser::Error;
+
+        let position = match error {
+            Error::Expected { span, .. } | Error::Unexpected { span, .. } => Some(span.start()),
+            Error::General { position, .. } => Some(*position),
+            Error::Lex {
+                err: boa_parser::lexer::Error::Syntax(_, position),
+            } => Some(*position),
+            _ => None,
+        };
+
+        Self {
+            severity: Severity::Error,
+            message: error.to_string(),
+            position,
+            notes: vec![],
+        }
+    }
+
+    pub fn render(&self, path: &Path, source: &str) -> String {
+        let mut output = match self.severity {
+            Severity::Error => format!("error: {}\n", self.message),
+            Severity::Warning => format!("warning: {}\n", self.message),
+        };
+
+        let Some(position) = self.position else {
+            let _ = writeln!(output, " --> {}", path.display());
+            self.render_notes(&mut output);
+            return output;
+        };
+
+        let line_number = position.line_number() as usize;
+        let column_number = position.column_number() as usize;
+        let _ = writeln!(
+            output,
+            " --> {}:{}:{}",
+            path.display(),
+            line_number,
+            column_number
+        );
+
+        if let Some(line) = source.lines().nth(line_number.saturating_sub(1)) {
+            let gutter = " ".repeat(line_number.to_string().len());
+            // Keep tabs in the marker line so the caret lines up with the
+            // snippet however the terminal renders them.
+            let padding: String = line
+                .chars()
+                .take(column_number.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            let _ = writeln!(output, "{} |", gutter);
+            let _ = writeln!(output, "{} | {}", line_number, line);
+            let _ = writeln!(output, "{} | {}^", gutter, padding);
+        }
+
+        self.render_notes(&mut output);
+
+        output
+    }
+
+    fn render_notes(&self, output: &mut String) {
+        for note in &self.notes {
+            let _ = writeln!(output, "  = note: {}", note);
+        }
+    }
+}
+
+// Renders diagnostics for a single source file to stderr and keeps count of
+// errors, so compilation can stop once `max_errors` have been reported.
+pub struct DiagnosticEmitter {
+    pub path: PathBuf,
+    pub source: String,
+    pub max_errors: Option<usize>,
+    pub error_count: usize,
+}
+
+impl DiagnosticEmitter {
+    pub fn new(path: &Path, source: &str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            source: source.to_string(),
+            max_errors: None,
+            error_count: 0,
+        }
+    }
+
+    pub fn emit(&mut self, diagnostic: &Diagnostic) -> Result<(), String> {
+        eprint!("{}", diagnostic.render(&self.path, &self.source));
+
+        if diagnostic.severity == Severity::Error {
+            self.error_count += 1;
+
+            if self.max_errors.is_some_and(|max| self.error_count >= max) {
+                return Err(self.abort_message());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<(), String> {
+        if self.error_count > 0 {
+            return Err(self.abort_message());
+        }
+
+        Ok(())
+    }
+
+    fn abort_message(&self) -> String {
+        match self.error_count {
+            1 => "aborting due to previous error".to_string(),
+            count => format!("aborting due to {} previous errors", count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boa_interner::Interner;
+    use boa_parser::{Parser, Source};
+
+    #[test]
+    fn renders_parse_errors_with_a_snippet() {
+        let source = "let a = 1;\nlet b = ;\n";
+        let Err(error) = Parser::new(Source::from_bytes(source)).parse_script(&mut Interner::new())
+        else {
+            panic!("expected a parse error");
+        };
+        let diagnostic = Diagnostic::from_parse_error(&error);
+
+        assert_eq!(diagnostic.position, Some(Position::new(2, 9)));
+        assert_eq!(
+            diagnostic.render(Path::new("main.js"), source),
+            format!(
+                "error: {}\n --> main.js:2:9\n  |\n2 | let b = ;\n  |         ^\n",
+                diagnostic.message
+            )
+        );
+    }
+
+    #[test]
+    fn renders_diagnostics_without_a_position() {
+        let diagnostic = Diagnostic::warning("something is off").with_note("try this");
+
+        assert_eq!(
+            diagnostic.render(Path::new("main.js"), ""),
+            "warning: something is off\n --> main.js\n  = note: try this\n"
+        );
+    }
+
+    #[test]
+    fn stops_after_max_errors() {
+        let mut emitter = DiagnosticEmitter::new(Path::new("main.js"), "");
+        emitter.max_errors = Some(2);
+
+        assert!(emitter.emit(&Diagnostic::error("first")).is_ok());
+        assert!(emitter.emit(&Diagnostic::warning("not an error")).is_ok());
+        assert_eq!(
+            emitter.emit(&Diagnostic::error("second")),
+            Err("aborting due to 2 previous errors".to_string())
+        );
+    }
+
+    #[test]
+    fn fails_to_finish_after_errors() {
+        let mut emitter = DiagnosticEmitter::new(Path::new("main.js"), "");
+        emitter
+            .emit(&Diagnostic::warning("only a warning"))
+            .unwrap();
+        assert!(emitter.finish().is_ok());
+
+        emitter.emit(&Diagnostic::error("an error")).unwrap();
+        assert_eq!(
+            emitter.finish(),
+            Err("aborting due to previous error".to_string())
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::output::write_atomic;
+use std::path::Path;
+
+// Exported functions are declared after the init function. They take and
+// return doubles, like every compiled function.
+pub fn write_header(
+    path: &Path,
+    entry_point: &str,
+    exports: &[(String, usize)],
+) -> Result<(), String> {
+    let guard = format!("{}_H", entry_point.to_uppercase());
+    let prototypes: String = exports
+        .iter()
+        .map(|(name, param_count)| {
+            let params = match param_count {
+                0 => "void".to_string(),
+                count => vec!["double"; *count].join(", "),
+            };
+
+            format!("double {}({});\n", name, params)
+        })
+        .collect();
+    let header = format!(
+        "#ifndef {guard}\n\
+         #define {guard}\n\
+         \n\
+         #ifdef __cplusplus\n\
+         extern \"C\" {{\n\
+         #endif\n\
+         \n\
+         int {entry_point}(void);\n\
+         {prototypes}\
+         \n\
+         #ifdef __cplusplus\n\
+         }}\n\
+         #endif\n\
+         \n\
+         #endif\n"
+    );
+
+    write_atomic(path, header.as_bytes())
+}
+
+pub fn c_identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+
+    identifier
+}
+
+// Keywords can't all be escaped as raw identifiers, and `ffi` is the module
+// holding the extern declarations.
+const RUST_RESERVED: &[&str] = &[
+    "_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "ffi", "final", "fn", "for", "gen",
+    "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv",
+    "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "try",
+    "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+// Whether a function can't be named `name` in the generated Rust bindings.
+pub fn is_reserved_in_rust(name: &str) -> bool {
+    RUST_RESERVED.contains(&name)
+}
+
+// Exports get a safe wrapper each, like the init function.
+pub fn write_rust_bindings(
+    path: &Path,
+    entry_point: &str,
+    exports: &[(String, usize)],
+) -> Result<(), String> {
+    let params = |param_count: usize| {
+        (0..param_count)
+            .map(|index| format!("arg{}: f64", index))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let declarations: String = exports
+        .iter()
+        .map(|(name, param_count)| {
+            format!(
+                "        pub fn {}({}) -> f64;\n",
+                name,
+                params(*param_count)
+            )
+        })
+        .collect();
+    let wrappers: String = exports
+        .iter()
+        .map(|(name, param_count)| {
+            let args = (0..*param_count)
+                .map(|index| format!("arg{}", index))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!(
+                "\npub fn {name}({}) -> f64 {{\n    \
+                     unsafe {{ ffi::{name}({args}) }}\n\
+                 }}\n",
+                params(*param_count)
+            )
+        })
+        .collect();
+    let bindings = format!(
+        "// Generated by jscc. Do not edit.\n\
+         \n\
+         mod ffi {{\n    \
+             extern \"C\" {{\n        \
+                 pub fn {entry_point}() -> i32;\n\
+                 {declarations}    \
+             }}\n\
+         }}\n\
+         \n\
+         pub fn {entry_point}() -> i32 {{\n    \
+             unsafe {{ ffi::{entry_point}() }}\n\
+         }}\n\
+         {wrappers}"
+    );
+
+    write_atomic(path, bindings.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs `write` on a file of its own and returns what it wrote.
+    fn written(name: &str, write: impl FnOnce(&Path) -> Result<(), String>) -> String {
+        let path = std::env::temp_dir().join(format!("jscc-{}-{}", std::process::id(), name));
+        write(&path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        contents
+    }
+
+    #[test]
+    fn writes_c_headers() {
+        let exports = [("add".to_string(), 2), ("js_tick".to_string(), 0)];
+        let header = written("header.h", |path| {
+            write_header(path, "my_app_init", &exports)
+        });
+
+        assert_eq!(
+            header,
+            "#ifndef MY_APP_INIT_H\n\
+             #define MY_APP_INIT_H\n\
+             \n\
+             #ifdef __cplusplus\n\
+             extern \"C\" {\n\
+             #endif\n\
+             \n\
+             int my_app_init(void);\n\
+             double add(double, double);\n\
+             double js_tick(void);\n\
+             \n\
+             #ifdef __cplusplus\n\
+             }\n\
+             #endif\n\
+             \n\
+             #endif\n"
+        );
+    }
+
+    #[test]
+    fn writes_rust_bindings() {
+        let exports = [("add".to_string(), 2), ("js_tick".to_string(), 0)];
+        let bindings = written("bindings.rs", |path| {
+            write_rust_bindings(path, "my_app_init", &exports)
+        });
+
+        assert_eq!(
+            bindings,
+            r#"// Generated by jscc. Do not edit.
+
+mod ffi {
+    extern "C" {
+        pub fn my_app_init() -> i32;
+        pub fn add(arg0: f64, arg1: f64) -> f64;
+        pub fn js_tick() -> f64;
+    }
+}
+
+pub fn my_app_init() -> i32 {
+    unsafe { ffi::my_app_init() }
+}
+
+pub fn add(arg0: f64, arg1: f64) -> f64 {
+    unsafe { ffi::add(arg0, arg1) }
+}
+
+pub fn js_tick() -> f64 {
+    unsafe { ffi::js_tick() }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn reserves_rust_keywords_and_the_ffi_module() {
+        for name in ["type", "fn", "Self", "_", "ffi"] {
+            assert!(is_reserved_in_rust(name), "{name}");
+        }
+        assert!(!is_reserved_in_rust("add"));
+        assert!(!is_reserved_in_rust("r#type"));
+    }
+
+    #[test]
+    fn mangles_c_identifiers() {
+        assert_eq!(c_identifier("my-app"), "my_app");
+        assert_eq!(c_identifier("2fa"), "_2fa");
+        assert_eq!(c_identifier("café.min"), "caf__min");
+        assert_eq!(c_identifier("already_valid1"), "already_valid1");
+    }
+}
@@ -0,0 +1,90 @@
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+thread_local! {
+    static CURRENT_ITEM: RefCell<Option<(usize, String)>> = const { RefCell::new(None) };
+}
+
+static PANIC_MESSAGE: Mutex<Option<String>> = Mutex::new(None);
+
+// Records which top-level item is being compiled, so a crash can point at the
+// code that triggered it.
+pub fn set_current_item(index: usize, source: String) {
+    CURRENT_ITEM.with(|item| *item.borrow_mut() = Some((index, source)));
+}
+
+pub fn clear_current_item() {
+    CURRENT_ITEM.with(|item| *item.borrow_mut() = None);
+}
+
+pub fn install_hook() {
+    panic::set_hook(Box::new(|info| {
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        *PANIC_MESSAGE.lock().unwrap() = Some(format!("{}\n\n{}", info, backtrace));
+    }));
+}
+
+pub fn catch<T>(input: &Path, f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(_) => Err(report(input)),
+    }
+}
+
+fn report(input: &Path) -> String {
+    let message = PANIC_MESSAGE
+        .lock()
+        .unwrap()
+        .take()
+        .unwrap_or_else(|| "unknown panic".to_string());
+    let item = CURRENT_ITEM.with(|item| item.borrow_mut().take());
+
+    let mut report = format!(
+        "jscc {} internal compiler error\n\ninput: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        input.display()
+    );
+
+    if let Some((index, source)) = &item {
+        report += &format!(
+            "while compiling top-level item {}:\n\n{}\n\n\
+             The item above on its own may be enough to reproduce the crash.\n",
+            index + 1,
+            source.trim_end()
+        );
+    }
+
+    report += &format!("\n{}\n", message);
+
+    let path = report_path();
+    let saved = match std::fs::write(&path, &report) {
+        Ok(()) => format!("A crash report was written to {}", path.display()),
+        Err(err) => format!("Failed to write a crash report: {}", err),
+    };
+
+    let summary = message.lines().next().unwrap_or_default();
+    eprintln!("error: internal compiler error: {}", summary);
+    if let Some((index, _)) = item {
+        eprintln!(
+            "note: while compiling top-level item {} of {}",
+            index + 1,
+            input.display()
+        );
+    }
+    eprintln!("note: jscc {}", env!("CARGO_PKG_VERSION"));
+    eprintln!("note: {}", saved);
+    eprintln!("note: please file a bug report with the crash report and the input attached");
+
+    "internal compiler error".to_string()
+}
+
+fn report_path() -> PathBuf {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    std::env::temp_dir().join(format!("jscc-ice-{}-{}.txt", timestamp, std::process::id()))
+}
@@ -1,11 +1,56 @@
+use boa_ast::declaration::{Binding, ExportDeclaration, LexicalDeclaration, Variable};
+use boa_ast::expression::access::{PropertyAccess, PropertyAccessField};
+use boa_ast::expression::literal::Literal;
+use boa_ast::expression::operator::assign::{AssignOp, AssignTarget};
+use boa_ast::expression::operator::binary::{
+    ArithmeticOp, BinaryOp, BitwiseOp, LogicalOp, RelationalOp,
+};
+use boa_ast::expression::operator::unary::UnaryOp;
+use boa_ast::expression::operator::update::{UpdateOp, UpdateTarget};
+use boa_ast::expression::operator::{Assign, Binary, Unary, Update};
+use boa_ast::expression::{Call, Identifier};
+use boa_ast::function::FunctionDeclaration;
+use boa_ast::statement::iteration::ForLoopInitializer;
+use boa_ast::statement::LabelledItem;
+use boa_ast::Declaration;
 use boa_ast::Expression;
 use boa_ast::ModuleItem;
+use boa_ast::Position;
 use boa_ast::Statement;
-use boa_interner::Interner;
+use boa_ast::StatementListItem;
+use boa_interner::{Interner, Sym, ToInternedString};
+use check::var_declarations;
+use diagnostics::Diagnostic;
+use header::{c_identifier, is_reserved_in_rust};
+use llvm_sys::analysis::LLVMVerifyFunction;
+use llvm_sys::bit_writer::LLVMWriteBitcodeToFile;
 use llvm_sys::core::*;
+use llvm_sys::error::{LLVMDisposeErrorMessage, LLVMGetErrorMessage};
 use llvm_sys::prelude::*;
-use llvm_sys::LLVMLinkage;
-use std::ffi::CString;
+use llvm_sys::target::*;
+use llvm_sys::target_machine::*;
+use llvm_sys::transforms::pass_builder::{
+    LLVMCreatePassBuilderOptions, LLVMDisposePassBuilderOptions, LLVMRunPasses,
+};
+use llvm_sys::{LLVMAttributeFunctionIndex, LLVMLinkage, LLVMTypeKind, LLVMVisibility};
+use pragma::Annotation;
+use scope::{BindingKind, Local, ScopeStack};
+use source_map::{function_head, statement_head, SourceMap};
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use target::Target;
+use tracing::instrument;
+
+pub mod check;
+pub mod diagnostics;
+pub mod header;
+pub mod link;
+pub mod output;
+pub mod pragma;
+pub mod scope;
+pub mod source_map;
+pub mod target;
+pub mod tsconfig;
 
 pub struct LLVMContext {
     pub context: LLVMContextRef,
@@ -27,7 +72,7 @@ impl LLVMContext {
 
             let mut param_types = vec![];
             let root_function_prototype = LLVMFunctionType(
-                LLVMVoidTypeInContext(context),
+                LLVMInt32TypeInContext(context),
                 param_types.as_mut_ptr(),
                 0,
                 0,
@@ -39,11 +84,11 @@ impl LLVMContext {
             let entry_block = {
                 let entry = CString::new("entry").unwrap();
 
-                LLVMAppendBasicBlock(root_function, entry.as_ptr())
+                LLVMAppendBasicBlockInContext(context, root_function, entry.as_ptr())
             };
 
             {
-                let mut param_types = vec![LLVMPointerType(LLVMInt8Type(), 0)];
+                let mut param_types = vec![LLVMPointerTypeInContext(context, 0)];
                 let puts_type = LLVMFunctionType(
                     LLVMInt32TypeInContext(context),
                     param_types.as_mut_ptr(),
@@ -76,6 +121,524 @@ impl LLVMContext {
             LLVMBuildGlobalStringPtr(self.builder, c_string.as_ptr(), str.as_ptr())
         }
     }
+
+    // Every JS number is an f64, whether it was written as an integer or not,
+    // so `1` and `1.0` share a type and can be mixed freely in arithmetic.
+    pub fn number_type(&self) -> LLVMTypeRef {
+        unsafe { LLVMDoubleTypeInContext(self.context) }
+    }
+
+    pub fn create_number_literal(&self, value: f64) -> LLVMValueRef {
+        unsafe { LLVMConstReal(self.number_type(), value) }
+    }
+
+    // There is no boxed value representation yet, so `undefined` is lowered
+    // to NaN, what it converts to as a number. That keeps it falsy, but it
+    // can't be told apart from a NaN number, so comparisons against it are
+    // reported as unsupported.
+    pub fn create_undefined(&self) -> LLVMValueRef {
+        self.create_number_literal(f64::NAN)
+    }
+
+    // What a slot holds before it is assigned. Only numbers can hold
+    // undefined, so string and boolean slots start out as "" and false, which
+    // are falsy like undefined.
+    pub fn create_unassigned(&self, ty: LLVMTypeRef) -> LLVMValueRef {
+        unsafe {
+            match LLVMGetTypeKind(ty) {
+                LLVMTypeKind::LLVMDoubleTypeKind => self.create_undefined(),
+                LLVMTypeKind::LLVMPointerTypeKind => self.create_string_literal(""),
+                _ => LLVMConstNull(ty),
+            }
+        }
+    }
+
+    // Allocas are placed at the start of the current function's entry block,
+    // where LLVM's mem2reg can promote them to registers.
+    pub fn build_entry_alloca(&self, ty: LLVMTypeRef, name: &str) -> LLVMValueRef {
+        unsafe {
+            let function = LLVMGetBasicBlockParent(LLVMGetInsertBlock(self.builder));
+            let entry = LLVMGetEntryBasicBlock(function);
+            let builder = LLVMCreateBuilderInContext(self.context);
+
+            let first_instruction = LLVMGetFirstInstruction(entry);
+            if first_instruction.is_null() {
+                LLVMPositionBuilderAtEnd(builder, entry);
+            } else {
+                LLVMPositionBuilderBefore(builder, first_instruction);
+            }
+
+            let name = CString::new(name).unwrap();
+            let slot = LLVMBuildAlloca(builder, ty, name.as_ptr());
+            LLVMDisposeBuilder(builder);
+
+            slot
+        }
+    }
+
+    pub fn get_or_declare_function(&self, name: &str, function_type: LLVMTypeRef) -> LLVMValueRef {
+        unsafe {
+            let c_name = CString::new(name).unwrap();
+            let function = LLVMGetNamedFunction(self.module, c_name.as_ptr());
+
+            if !function.is_null() {
+                return function;
+            }
+
+            let function = LLVMAddFunction(self.module, c_name.as_ptr(), function_type);
+            LLVMSetLinkage(function, LLVMLinkage::LLVMExternalLinkage);
+
+            function
+        }
+    }
+
+    // Booleans are i1 and strings are pointers to NUL-terminated bytes.
+    // Strings go through strtod, which skips leading whitespace. Like
+    // `Number()`, the string is NaN unless only whitespace follows the number,
+    // and a blank string is 0.
+    pub fn build_to_number(&self, value: LLVMValueRef) -> LLVMValueRef {
+        unsafe {
+            let ty = LLVMTypeOf(value);
+
+            match LLVMGetTypeKind(ty) {
+                LLVMTypeKind::LLVMDoubleTypeKind => value,
+                LLVMTypeKind::LLVMIntegerTypeKind if LLVMGetIntTypeWidth(ty) == 1 => {
+                    LLVMBuildUIToFP(self.builder, value, self.number_type(), c"".as_ptr())
+                }
+                LLVMTypeKind::LLVMIntegerTypeKind => {
+                    LLVMBuildSIToFP(self.builder, value, self.number_type(), c"".as_ptr())
+                }
+                LLVMTypeKind::LLVMPointerTypeKind => {
+                    let pointer_type = LLVMPointerTypeInContext(self.context, 0);
+                    let mut param_types = vec![pointer_type, pointer_type];
+                    let strtod_type = LLVMFunctionType(
+                        self.number_type(),
+                        param_types.as_mut_ptr(),
+                        param_types.len() as u32,
+                        0,
+                    );
+                    let strtod = self.get_or_declare_function("strtod", strtod_type);
+                    let end_slot = self.build_entry_alloca(pointer_type, "end");
+                    let mut args = vec![value, end_slot];
+
+                    let number = LLVMBuildCall2(
+                        self.builder,
+                        strtod_type,
+                        strtod,
+                        args.as_mut_ptr(),
+                        args.len() as u32,
+                        c"".as_ptr(),
+                    );
+                    let end = LLVMBuildLoad2(self.builder, pointer_type, end_slot, c"".as_ptr());
+
+                    // A blank string leaves `end` at the start, where the
+                    // whitespace is skipped like a trailing one.
+                    let size_type = LLVMInt64TypeInContext(self.context);
+                    let strspn_type = LLVMFunctionType(
+                        size_type,
+                        param_types.as_mut_ptr(),
+                        param_types.len() as u32,
+                        0,
+                    );
+                    let strspn = self.get_or_declare_function("strspn", strspn_type);
+                    let mut args = vec![end, self.create_string_literal(" \t\n\x0b\x0c\r")];
+                    let whitespace = LLVMBuildCall2(
+                        self.builder,
+                        strspn_type,
+                        strspn,
+                        args.as_mut_ptr(),
+                        args.len() as u32,
+                        c"".as_ptr(),
+                    );
+
+                    let byte_type = LLVMInt8TypeInContext(self.context);
+                    let mut indices = vec![whitespace];
+                    let rest = LLVMBuildGEP2(
+                        self.builder,
+                        byte_type,
+                        end,
+                        indices.as_mut_ptr(),
+                        indices.len() as u32,
+                        c"".as_ptr(),
+                    );
+                    let rest = LLVMBuildLoad2(self.builder, byte_type, rest, c"".as_ptr());
+                    let is_number = LLVMBuildICmp(
+                        self.builder,
+                        llvm_sys::LLVMIntPredicate::LLVMIntEQ,
+                        rest,
+                        LLVMConstInt(byte_type, 0, 0),
+                        c"".as_ptr(),
+                    );
+
+                    LLVMBuildSelect(
+                        self.builder,
+                        is_number,
+                        number,
+                        self.create_number_literal(f64::NAN),
+                        c"".as_ptr(),
+                    )
+                }
+                _ => panic!("Cannot convert value to a number"),
+            }
+        }
+    }
+
+    // `===` on unboxed values. Apart from integer and double numbers, values of
+    // different LLVM types are of different JS types, so they are never equal.
+    pub fn build_strict_equals(&self, lhs: LLVMValueRef, rhs: LLVMValueRef) -> LLVMValueRef {
+        unsafe {
+            let ty = LLVMTypeOf(lhs);
+            let bool_type = LLVMInt1TypeInContext(self.context);
+
+            if ty != LLVMTypeOf(rhs) {
+                if !is_number_type(ty) || !is_number_type(LLVMTypeOf(rhs)) {
+                    return LLVMConstInt(bool_type, 0, 0);
+                }
+
+                return LLVMBuildFCmp(
+                    self.builder,
+                    llvm_sys::LLVMRealPredicate::LLVMRealOEQ,
+                    self.build_to_number(lhs),
+                    self.build_to_number(rhs),
+                    c"".as_ptr(),
+                );
+            }
+
+            match LLVMGetTypeKind(ty) {
+                LLVMTypeKind::LLVMDoubleTypeKind => LLVMBuildFCmp(
+                    self.builder,
+                    llvm_sys::LLVMRealPredicate::LLVMRealOEQ,
+                    lhs,
+                    rhs,
+                    c"".as_ptr(),
+                ),
+                LLVMTypeKind::LLVMIntegerTypeKind => LLVMBuildICmp(
+                    self.builder,
+                    llvm_sys::LLVMIntPredicate::LLVMIntEQ,
+                    lhs,
+                    rhs,
+                    c"".as_ptr(),
+                ),
+                LLVMTypeKind::LLVMPointerTypeKind => {
+                    let order = self.build_strcmp(lhs, rhs);
+
+                    LLVMBuildICmp(
+                        self.builder,
+                        llvm_sys::LLVMIntPredicate::LLVMIntEQ,
+                        order,
+                        LLVMConstNull(LLVMTypeOf(order)),
+                        c"".as_ptr(),
+                    )
+                }
+                _ => panic!("Cannot compare values"),
+            }
+        }
+    }
+
+    // Orders two strings by their bytes, returning an i32 like strcmp does.
+    pub fn build_strcmp(&self, lhs: LLVMValueRef, rhs: LLVMValueRef) -> LLVMValueRef {
+        unsafe {
+            let pointer_type = LLVMTypeOf(lhs);
+            let mut param_types = vec![pointer_type, pointer_type];
+            let strcmp_type = LLVMFunctionType(
+                LLVMInt32TypeInContext(self.context),
+                param_types.as_mut_ptr(),
+                param_types.len() as u32,
+                0,
+            );
+            let strcmp = self.get_or_declare_function("strcmp", strcmp_type);
+            let mut args = vec![lhs, rhs];
+
+            LLVMBuildCall2(
+                self.builder,
+                strcmp_type,
+                strcmp,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                c"".as_ptr(),
+            )
+        }
+    }
+
+    // fptosi is poison for NaN and out of range values, so conversions go
+    // through the saturating intrinsic, which maps NaN to 0.
+    pub fn build_saturating_to_i64(&self, value: LLVMValueRef) -> LLVMValueRef {
+        unsafe {
+            let int_type = LLVMInt64TypeInContext(self.context);
+            let mut param_types = vec![self.number_type()];
+            let function_type = LLVMFunctionType(
+                int_type,
+                param_types.as_mut_ptr(),
+                param_types.len() as u32,
+                0,
+            );
+            let function = self.get_or_declare_function("llvm.fptosi.sat.i64.f64", function_type);
+            let mut args = vec![value];
+
+            LLVMBuildCall2(
+                self.builder,
+                function_type,
+                function,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                c"".as_ptr(),
+            )
+        }
+    }
+
+    // Calls a math intrinsic such as `llvm.fabs.f64` on a number.
+    fn build_math_intrinsic(&self, name: &str, value: LLVMValueRef) -> LLVMValueRef {
+        unsafe {
+            let mut param_types = vec![self.number_type()];
+            let function_type = LLVMFunctionType(
+                self.number_type(),
+                param_types.as_mut_ptr(),
+                param_types.len() as u32,
+                0,
+            );
+            let function = self.get_or_declare_function(name, function_type);
+            let mut args = vec![value];
+
+            LLVMBuildCall2(
+                self.builder,
+                function_type,
+                function,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                c"".as_ptr(),
+            )
+        }
+    }
+
+    // ToInt32: non-finite values become 0, and the rest are truncated toward
+    // zero and wrap modulo 2^32. The remainder is small enough to convert to
+    // an i64 exactly, and truncating that to 32 bits finishes the wrapping.
+    pub fn build_to_int32(&self, value: LLVMValueRef) -> LLVMValueRef {
+        unsafe {
+            let value = self.build_to_number(value);
+            let magnitude = self.build_math_intrinsic("llvm.fabs.f64", value);
+            let is_finite = LLVMBuildFCmp(
+                self.builder,
+                llvm_sys::LLVMRealPredicate::LLVMRealOLT,
+                magnitude,
+                self.create_number_literal(f64::INFINITY),
+                c"".as_ptr(),
+            );
+            let value = LLVMBuildSelect(
+                self.builder,
+                is_finite,
+                value,
+                self.create_number_literal(0.0),
+                c"".as_ptr(),
+            );
+
+            let value = self.build_math_intrinsic("llvm.trunc.f64", value);
+            let value = LLVMBuildFRem(
+                self.builder,
+                value,
+                self.create_number_literal(4294967296.0),
+                c"".as_ptr(),
+            );
+            let value = LLVMBuildFPToSI(
+                self.builder,
+                value,
+                LLVMInt64TypeInContext(self.context),
+                c"".as_ptr(),
+            );
+
+            LLVMBuildTrunc(
+                self.builder,
+                value,
+                LLVMInt32TypeInContext(self.context),
+                c"".as_ptr(),
+            )
+        }
+    }
+
+    // Follows JS truthiness: 0, NaN and the empty string are false.
+    pub fn build_to_boolean(&self, value: LLVMValueRef) -> LLVMValueRef {
+        unsafe {
+            let ty = LLVMTypeOf(value);
+
+            match LLVMGetTypeKind(ty) {
+                LLVMTypeKind::LLVMIntegerTypeKind if LLVMGetIntTypeWidth(ty) == 1 => value,
+                LLVMTypeKind::LLVMIntegerTypeKind => LLVMBuildICmp(
+                    self.builder,
+                    llvm_sys::LLVMIntPredicate::LLVMIntNE,
+                    value,
+                    LLVMConstNull(ty),
+                    c"".as_ptr(),
+                ),
+                // An ordered comparison is false for NaN.
+                LLVMTypeKind::LLVMDoubleTypeKind => LLVMBuildFCmp(
+                    self.builder,
+                    llvm_sys::LLVMRealPredicate::LLVMRealONE,
+                    value,
+                    LLVMConstNull(ty),
+                    c"".as_ptr(),
+                ),
+                LLVMTypeKind::LLVMPointerTypeKind => {
+                    let byte_type = LLVMInt8TypeInContext(self.context);
+                    let first_byte = LLVMBuildLoad2(self.builder, byte_type, value, c"".as_ptr());
+
+                    LLVMBuildICmp(
+                        self.builder,
+                        llvm_sys::LLVMIntPredicate::LLVMIntNE,
+                        first_byte,
+                        LLVMConstNull(byte_type),
+                        c"".as_ptr(),
+                    )
+                }
+                _ => panic!("Cannot convert value to a boolean"),
+            }
+        }
+    }
+
+    pub fn create_bytes_literal(&self, bytes: &[u8]) -> LLVMValueRef {
+        unsafe {
+            let value = LLVMConstStringInContext(
+                self.context,
+                bytes.as_ptr() as *const _,
+                bytes.len() as u32,
+                0,
+            );
+            let global = LLVMAddGlobal(self.module, LLVMTypeOf(value), c"bytes".as_ptr());
+
+            LLVMSetInitializer(global, value);
+            LLVMSetGlobalConstant(global, 1);
+            LLVMSetLinkage(global, LLVMLinkage::LLVMPrivateLinkage);
+
+            global
+        }
+    }
+
+    pub fn set_root_function_name(&self, name: &str) {
+        unsafe {
+            LLVMSetValueName2(self.root_function, name.as_ptr() as *const _, name.len());
+        }
+    }
+
+    fn create_target_machine(&self, target: &Target) -> Result<LLVMTargetMachineRef, String> {
+        unsafe {
+            LLVM_InitializeAllTargetInfos();
+            LLVM_InitializeAllTargets();
+            LLVM_InitializeAllTargetMCs();
+            LLVM_InitializeAllAsmPrinters();
+
+            let triple = CString::new(target.triple.as_str()).unwrap();
+            let mut llvm_target = std::ptr::null_mut();
+            let mut error = std::ptr::null_mut();
+
+            if LLVMGetTargetFromTriple(triple.as_ptr(), &mut llvm_target, &mut error) != 0 {
+                let message = CStr::from_ptr(error).to_string_lossy().into_owned();
+                LLVMDisposeMessage(error);
+
+                return Err(format!("Unknown target {}: {}", target.triple, message));
+            }
+
+            let target_machine = LLVMCreateTargetMachine(
+                llvm_target,
+                triple.as_ptr(),
+                c"generic".as_ptr(),
+                c"".as_ptr(),
+                LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
+                if target.pic {
+                    LLVMRelocMode::LLVMRelocPIC
+                } else {
+                    LLVMRelocMode::LLVMRelocStatic
+                },
+                LLVMCodeModel::LLVMCodeModelDefault,
+            );
+
+            LLVMSetTarget(self.module, triple.as_ptr());
+            let data_layout = LLVMCreateTargetDataLayout(target_machine);
+            LLVMSetModuleDataLayout(self.module, data_layout);
+            LLVMDisposeTargetData(data_layout);
+
+            Ok(target_machine)
+        }
+    }
+
+    fn emit_to_file(
+        &self,
+        target: &Target,
+        path: &Path,
+        file_type: LLVMCodeGenFileType,
+    ) -> Result<(), String> {
+        let target_machine = self.create_target_machine(target)?;
+
+        unsafe {
+            let mut error = std::ptr::null_mut();
+            let path = CString::new(path.to_string_lossy().as_bytes()).unwrap();
+            let failed = LLVMTargetMachineEmitToFile(
+                target_machine,
+                self.module,
+                path.as_ptr(),
+                file_type,
+                &mut error,
+            );
+            LLVMDisposeTargetMachine(target_machine);
+
+            if failed != 0 {
+                let message = CStr::from_ptr(error).to_string_lossy().into_owned();
+                LLVMDisposeMessage(error);
+
+                return Err(message);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(target = %target.triple, path = %path.display()))]
+    pub fn write_object_file(&self, target: &Target, path: &Path) -> Result<(), String> {
+        self.emit_to_file(target, path, LLVMCodeGenFileType::LLVMObjectFile)
+            .map_err(|err| format!("Failed to emit object file: {}", err))
+    }
+
+    #[instrument(skip_all, fields(target = %target.triple, path = %path.display()))]
+    pub fn write_assembly_file(&self, target: &Target, path: &Path) -> Result<(), String> {
+        self.emit_to_file(target, path, LLVMCodeGenFileType::LLVMAssemblyFile)
+            .map_err(|err| format!("Failed to emit assembly: {}", err))
+    }
+
+    #[instrument(skip_all, fields(target = %target.triple, path = %path.display()))]
+    pub fn write_llvm_ir(&self, target: &Target, path: &Path) -> Result<(), String> {
+        let target_machine = self.create_target_machine(target)?;
+
+        unsafe {
+            LLVMDisposeTargetMachine(target_machine);
+
+            let mut error = std::ptr::null_mut();
+            let path = CString::new(path.to_string_lossy().as_bytes()).unwrap();
+
+            if LLVMPrintModuleToFile(self.module, path.as_ptr(), &mut error) != 0 {
+                let message = CStr::from_ptr(error).to_string_lossy().into_owned();
+                LLVMDisposeMessage(error);
+
+                return Err(format!("Failed to emit LLVM IR: {}", message));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(target = %target.triple, path = %path.display()))]
+    pub fn write_bitcode(&self, target: &Target, path: &Path) -> Result<(), String> {
+        let target_machine = self.create_target_machine(target)?;
+
+        unsafe {
+            LLVMDisposeTargetMachine(target_machine);
+
+            let path = CString::new(path.to_string_lossy().as_bytes()).unwrap();
+
+            if LLVMWriteBitcodeToFile(self.module, path.as_ptr()) != 0 {
+                return Err("Failed to emit LLVM bitcode".to_string());
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Drop for LLVMContext {
@@ -88,185 +651,3177 @@ impl Drop for LLVMContext {
     }
 }
 
+// C functions generated code calls by name. An export with one of these
+// symbols would be called in their place.
+const RUNTIME_SYMBOLS: &[&str] = &["strcmp", "strspn", "strtod", "write"];
+
+// What to do with a construct the compiler can't lower yet.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnsupportedPolicy {
+    #[default]
+    Error,
+    // Report a warning and compile the construct as a runtime trap.
+    Warn,
+    // Compile the construct as a runtime trap without reporting it.
+    Trap,
+}
+
+enum JumpKind {
+    Loop { continue_block: LLVMBasicBlockRef },
+    Switch,
+    // A labelled statement that isn't a loop can only be left with a
+    // labelled `break`.
+    Labelled,
+}
+
+// Where `break` and `continue` inside a loop, switch or labelled statement
+// branch to.
+struct JumpTarget {
+    labels: Vec<Sym>,
+    kind: JumpKind,
+    break_block: LLVMBasicBlockRef,
+}
+
+// The locals of a function that nested functions use. Functions can't escape
+// the call that declared them yet, so the environment lives on the stack and
+// is passed to nested functions as a hidden first parameter.
+struct Environment {
+    function: LLVMValueRef,
+    // An array holding the enclosing function's environment followed by
+    // pointers to the captured slots, created once something needs it.
+    record: Option<LLVMValueRef>,
+    captured: Vec<LLVMValueRef>,
+}
+
+impl Environment {
+    fn new(function: LLVMValueRef) -> Self {
+        Self {
+            function,
+            record: None,
+            captured: vec![],
+        }
+    }
+}
+
 pub struct CodeGenerator {
     pub context: LLVMContext,
+    pub scopes: ScopeStack,
+    // One environment per function being compiled, with top-level code first.
+    environments: Vec<Environment>,
+    jump_targets: Vec<JumpTarget>,
+    // Labels of a labelled loop, waiting for the loop to claim them.
+    pending_labels: Vec<Sym>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub base_dir: PathBuf,
+    // The input as named in runtime trap messages.
+    pub source_name: String,
+    pub source_map: SourceMap,
+    // Where the statement being compiled starts, if it could be found.
+    position: Option<Position>,
+    pub verify_each: bool,
+    pub unsupported: UnsupportedPolicy,
+    // Web-compatible semantics for function declarations in blocks, for
+    // sloppy mode code.
+    pub annex_b: bool,
+    // `/* @jscc ... */` annotations, by the position of the declaration they
+    // apply to.
+    pub annotations: Vec<(Position, Annotation)>,
+    // The symbol and parameter count of each visible exported function.
+    pub exports: Vec<(String, usize)>,
+    // Rust bindings reserve more names than the C header.
+    pub rust_bindings: bool,
+    pub opt_level: u8,
 }
 
 impl Default for CodeGenerator {
     fn default() -> Self {
+        let context = LLVMContext::new("main");
+        let root_environment = Environment::new(context.root_function);
+
         Self {
-            context: LLVMContext::new("main"),
+            context,
+            scopes: ScopeStack::default(),
+            environments: vec![root_environment],
+            jump_targets: vec![],
+            pending_labels: vec![],
+            diagnostics: vec![],
+            base_dir: PathBuf::from("."),
+            source_name: String::new(),
+            source_map: SourceMap::default(),
+            position: None,
+            verify_each: false,
+            unsupported: UnsupportedPolicy::default(),
+            annex_b: false,
+            annotations: vec![],
+            exports: vec![],
+            rust_bindings: false,
+            opt_level: 0,
         }
     }
 }
 
 impl CodeGenerator {
+    #[instrument(skip_all)]
     pub fn compile_module_item(
         &mut self,
         module_item: &ModuleItem,
         interner: &Interner,
     ) -> Option<LLVMValueRef> {
-        match module_item {
-            ModuleItem::ImportDeclaration(_) => todo!(),
-            ModuleItem::ExportDeclaration(_) => todo!(),
-            ModuleItem::StatementListItem(sli) => match sli {
-                boa_ast::StatementListItem::Statement(statement) => {
-                    self.compile_statement(statement, interner)
-                }
-                boa_ast::StatementListItem::Declaration(_declaration) => todo!(),
-            },
-        }
+        let value = match module_item {
+            ModuleItem::ImportDeclaration(_) => Some(self.unsupported("import declarations")),
+            // Exported function bodies are compiled with the other top-level
+            // ones.
+            ModuleItem::ExportDeclaration(ExportDeclaration::Declaration(
+                Declaration::FunctionDeclaration(_),
+            )) => None,
+            ModuleItem::ExportDeclaration(_) => Some(self.unsupported("export declarations")),
+            ModuleItem::StatementListItem(sli) => self.compile_statement_list_item(sli, interner),
+        };
+
+        self.finish_top_level_item();
+
+        value
     }
 
-    pub fn compile_expression(
+    #[instrument(skip_all)]
+    pub fn compile_script_item(
         &mut self,
-        expression: &Expression,
+        item: &StatementListItem,
         interner: &Interner,
     ) -> Option<LLVMValueRef> {
-        match expression {
-            Expression::This => todo!(),
-            Expression::Identifier(_) => todo!(),
-            Expression::Literal(literal) => match literal {
-                boa_ast::expression::literal::Literal::String(string) => {
-                    let string_value = interner.resolve_expect(*string).utf8().unwrap();
-
-                    dbg!(string_value);
-
-                    Some(self.context.create_string_literal(string_value))
-                }
-                boa_ast::expression::literal::Literal::Num(n) => todo!(),
-                boa_ast::expression::literal::Literal::Int(n) => Some(unsafe {
-                    LLVMConstInt(LLVMInt32TypeInContext(self.context.context), *n as u64, 0)
-                }),
-                boa_ast::expression::literal::Literal::BigInt(_) => todo!(),
-                boa_ast::expression::literal::Literal::Bool(_) => todo!(),
-                boa_ast::expression::literal::Literal::Null => todo!(),
-                boa_ast::expression::literal::Literal::Undefined => todo!(),
-            },
-            Expression::RegExpLiteral(_) => todo!(),
-            Expression::ArrayLiteral(_) => todo!(),
-            Expression::ObjectLiteral(_) => todo!(),
-            Expression::Spread(_) => todo!(),
-            Expression::FunctionExpression(function) => todo!(),
-            Expression::ArrowFunction(_) => todo!(),
-            Expression::AsyncArrowFunction(_) => todo!(),
-            Expression::GeneratorExpression(_) => todo!(),
-            Expression::AsyncFunctionExpression(_) => todo!(),
-            Expression::AsyncGeneratorExpression(_) => todo!(),
-            Expression::ClassExpression(_) => todo!(),
-            Expression::TemplateLiteral(_) => todo!(),
-            Expression::PropertyAccess(_) => todo!(),
-            Expression::New(_) => todo!(),
-            Expression::Call(call) => {
-                let identifier = match call.function() {
-                    Expression::Identifier(ident) => {
-                        interner.resolve_expect(ident.sym()).utf8().unwrap()
-                    }
-                    i => panic!("Unknown function identifier: {:#?}", i),
-                };
-
-                let mut args = vec![];
-                for arg in call.args() {
-                    args.push(self.compile_expression(arg, interner).unwrap());
-                }
-
-                let mut arg_types = vec![];
-
-                for arg in &args {
-                    arg_types.push(unsafe { LLVMTypeOf(*arg) });
-                }
+        let value = self.compile_statement_list_item(item, interner);
 
-                let function = unsafe {
-                    let c_name = CString::new(identifier).unwrap();
-                    let function = LLVMGetNamedFunction(self.context.module, c_name.as_ptr());
-
-                    let function_type = LLVMFunctionType(
-                        LLVMInt32TypeInContext(self.context.context),
-                        arg_types.as_mut_ptr(),
-                        arg_types.len() as u32,
-                        0,
-                    );
+        self.finish_top_level_item();
 
-                    if function.is_null() {
-                        let function =
-                            LLVMAddFunction(self.context.module, c_name.as_ptr(), function_type);
+        value
+    }
 
-                        LLVMSetLinkage(function, LLVMLinkage::LLVMExternalLinkage);
+    fn finish_top_level_item(&self) {
+        if self.verify_each {
+            self.check_builder_position()
+                .unwrap_or_else(|err| panic!("Codegen invariant violated: {}", err));
 
-                        (function, function_type)
-                    } else {
-                        (function, function_type)
-                    }
-                };
+            assert_eq!(
+                self.scopes.depth(),
+                1,
+                "Codegen invariant violated: scope stack is unbalanced"
+            );
+        }
+    }
 
-                Some(unsafe {
-                    LLVMBuildCall2(
-                        self.context.builder,
-                        function.1,
-                        function.0,
-                        args.as_mut_ptr(),
-                        args.len() as u32,
-                        b"\0".as_ptr(),
-                    )
-                })
+    // Function declarations are hoisted, so every function in a statement list
+    // gets its prototype before any of the list is compiled and can be called
+    // ahead of its declaration.
+    pub fn declare_functions<'a>(
+        &mut self,
+        items: impl IntoIterator<Item = &'a StatementListItem>,
+        interner: &Interner,
+    ) {
+        for item in items {
+            if let StatementListItem::Declaration(Declaration::FunctionDeclaration(function)) = item
+            {
+                self.declare_function(function, interner);
             }
-            Expression::SuperCall(_) => todo!(),
-            Expression::ImportCall(_) => todo!(),
-            Expression::Optional(_) => todo!(),
-            Expression::TaggedTemplate(_) => todo!(),
-            Expression::NewTarget => todo!(),
-            Expression::ImportMeta => todo!(),
-            Expression::Assign(_) => todo!(),
-            Expression::Unary(_) => todo!(),
-            Expression::Update(_) => todo!(),
-            Expression::Binary(_) => todo!(),
-            Expression::BinaryInPrivate(_) => todo!(),
-            Expression::Conditional(_) => todo!(),
-            Expression::Await(_) => todo!(),
-            Expression::Yield(_) => todo!(),
-            Expression::Parenthesized(_) => todo!(),
-            _ => todo!(),
         }
     }
 
-    pub fn compile_statement(
+    // Function bodies are compiled after the rest of the statement list that
+    // declares them, so they can use bindings declared later in the list.
+    pub fn compile_function_bodies<'a>(
         &mut self,
-        statement: &Statement,
-
+        items: impl IntoIterator<Item = &'a StatementListItem>,
         interner: &Interner,
-    ) -> Option<LLVMValueRef> {
+    ) {
+        for item in items {
+            if let StatementListItem::Declaration(Declaration::FunctionDeclaration(function)) = item
+            {
+                self.compile_function_declaration(function, interner);
+            }
+        }
+    }
+
+    pub fn compile_exported_function_bodies<'a>(
+        &mut self,
+        items: impl IntoIterator<Item = &'a ModuleItem>,
+        interner: &Interner,
+    ) {
+        for function in items.into_iter().filter_map(exported_function) {
+            self.compile_function_declaration(function, interner);
+        }
+    }
+
+    // `var` declarations are hoisted to the top of their function, where they
+    // get a slot holding undefined. Slots are statically typed, so each takes
+    // the type of the first of its initializers whose type is known, and is a
+    // number otherwise. Initializers of conflicting types are reported when
+    // they are stored.
+    pub fn declare_vars<'a>(
+        &mut self,
+        items: impl IntoIterator<Item = &'a StatementListItem>,
+        interner: &Interner,
+    ) {
+        let declarations = var_declarations(items);
+        let bound_identifier = |variable: &Variable| match variable.binding() {
+            Binding::Identifier(identifier) => Some(*identifier),
+            Binding::Pattern(_) => None,
+        };
+
+        for identifier in declarations
+            .iter()
+            .filter_map(|&variable| bound_identifier(variable))
+        {
+            if self.scopes.lookup_var(identifier.sym()).is_some() {
+                continue;
+            }
+
+            let name = interner.resolve_expect(identifier.sym()).utf8().unwrap();
+            let ty = declarations
+                .iter()
+                .filter(|&&variable| bound_identifier(variable) == Some(identifier))
+                .filter_map(|variable| variable.init())
+                .find_map(|init| self.static_type(init, interner))
+                .unwrap_or_else(|| self.context.number_type());
+            let slot = self.context.build_entry_alloca(ty, name);
+
+            unsafe {
+                LLVMBuildStore(
+                    self.context.builder,
+                    self.context.create_unassigned(ty),
+                    slot,
+                );
+            }
+
+            let local = Local {
+                slot,
+                ty,
+                kind: BindingKind::Var,
+            };
+            // Conflicts with `let` and `const` are early errors, reported
+            // before codegen.
+            self.scopes.declare_var(identifier.sym(), local);
+        }
+    }
+
+    // Exported functions are hoisted like any other, but keep external linkage
+    // so C code can call them. Their signatures are collected for the
+    // generated header unless they are hidden.
+    pub fn declare_exported_functions<'a>(
+        &mut self,
+        items: impl IntoIterator<Item = &'a ModuleItem>,
+        interner: &Interner,
+    ) {
+        for function in items.into_iter().filter_map(exported_function) {
+            let llvm_function = self.declare_function(function, interner);
+
+            // Exports are named after the JS function unless annotated
+            // otherwise.
+            let name = interner
+                .resolve_expect(function.name().sym())
+                .utf8()
+                .unwrap();
+            let annotations = self.annotations_of(function, interner);
+            let hidden = annotations.contains(&&Annotation::Hidden);
+            let symbol = annotations
+                .iter()
+                .find_map(|annotation| match annotation {
+                    Annotation::ExportName(symbol) => Some(symbol.clone()),
+                    _ => None,
+                })
+                .unwrap_or_else(|| name.to_string());
+
+            // The symbol is declared in the generated header, and in the Rust
+            // bindings when those are emitted.
+            let invalid = if c_identifier(&symbol) != symbol {
+                Some("is not a valid C identifier")
+            } else if self.rust_bindings && is_reserved_in_rust(&symbol) {
+                Some("is reserved in the generated Rust bindings")
+            } else {
+                None
+            };
+            if let Some(problem) = invalid {
+                self.position = self
+                    .source_map
+                    .locate_next(&function_head(function, interner));
+                self.push_diagnostic(
+                    Diagnostic::error(format!("The symbol `{}` {}", symbol, problem)).with_note(
+                        "give the function another symbol with `/* @jscc export_name(\"<symbol>\") */`",
+                    ),
+                );
+
+                continue;
+            }
+
+            // LLVM would quietly rename the export instead of failing to link.
+            let c_symbol = CString::new(symbol.as_str()).unwrap();
+            let defined =
+                unsafe { !LLVMGetNamedFunction(self.context.module, c_symbol.as_ptr()).is_null() };
+            if defined || RUNTIME_SYMBOLS.contains(&symbol.as_str()) {
+                self.position = self
+                    .source_map
+                    .locate_next(&function_head(function, interner));
+                self.push_diagnostic(
+                    Diagnostic::error(format!("The symbol `{}` is already defined", symbol))
+                        .with_note(
+                            "give the function another symbol with `/* @jscc export_name(\"<symbol>\") */`",
+                        ),
+                );
+
+                continue;
+            }
+
+            unsafe {
+                LLVMSetValueName2(llvm_function, symbol.as_ptr() as *const _, symbol.len());
+                LLVMSetLinkage(llvm_function, LLVMLinkage::LLVMExternalLinkage);
+
+                if hidden {
+                    LLVMSetVisibility(llvm_function, LLVMVisibility::LLVMHiddenVisibility);
+                } else {
+                    self.exports
+                        .push((symbol, LLVMCountParams(llvm_function) as usize));
+                }
+            }
+        }
+    }
+
+    // User functions take and return numbers until there is a boxed value
+    // representation. Functions nested in another function take its
+    // environment as their first parameter. Top-level functions don't need
+    // one, since the top-level locals they use are globals, so they can be
+    // called from C.
+    fn declare_function(
+        &mut self,
+        function: &FunctionDeclaration,
+        interner: &Interner,
+    ) -> LLVMValueRef {
+        let sym = function.name().sym();
+        if let Some(llvm_function) = self.scopes.hoisted_function(sym) {
+            return llvm_function;
+        }
+
+        let name = interner.resolve_expect(sym).utf8().unwrap();
+        // The prefix keeps JS functions from colliding with C functions the
+        // runtime calls by name, such as `strtod`.
+        let symbol = CString::new(format!("js.{}", name)).unwrap();
+        let mut param_types =
+            vec![self.context.number_type(); function.parameters().as_ref().len()];
+        if self.environments.len() > 1 {
+            param_types.insert(0, unsafe {
+                LLVMPointerTypeInContext(self.context.context, 0)
+            });
+        }
+
+        let llvm_function = unsafe {
+            let function_type = LLVMFunctionType(
+                self.context.number_type(),
+                param_types.as_mut_ptr(),
+                param_types.len() as u32,
+                0,
+            );
+            let llvm_function =
+                LLVMAddFunction(self.context.module, symbol.as_ptr(), function_type);
+            LLVMSetLinkage(llvm_function, LLVMLinkage::LLVMInternalLinkage);
+            if self.environments.len() > 1 {
+                LLVMSetValueName2(LLVMGetParam(llvm_function, 0), c"env".as_ptr(), 3);
+            }
+
+            llvm_function
+        };
+
+        for annotation in self.annotations_of(function, interner) {
+            match annotation {
+                Annotation::Inline => unsafe {
+                    let kind = LLVMGetEnumAttributeKindForName(c"alwaysinline".as_ptr(), 12);
+                    let attribute = LLVMCreateEnumAttribute(self.context.context, kind, 0);
+                    LLVMAddAttributeAtIndex(llvm_function, LLVMAttributeFunctionIndex, attribute);
+                },
+                // Applied once the function is exported, since local linkage
+                // requires default visibility.
+                Annotation::ExportName(_) | Annotation::Hidden => {}
+            }
+        }
+
+        self.scopes.declare_function(sym, llvm_function);
+
+        llvm_function
+    }
+
+    // Annotations are found by the position of the declaration, which is
+    // searched for from the construct compiled last, as functions are
+    // declared before the code around them is compiled.
+    fn annotations_of(
+        &self,
+        function: &FunctionDeclaration,
+        interner: &Interner,
+    ) -> Vec<&Annotation> {
+        let Some(position) = self.source_map.find(&function_head(function, interner)) else {
+            return vec![];
+        };
+
+        self.annotations
+            .iter()
+            .filter(|(annotated, _)| *annotated == position)
+            .map(|(_, annotation)| annotation)
+            .collect()
+    }
+
+    #[instrument(skip_all, fields(name = %interner.resolve_expect(function.name().sym())))]
+    fn compile_function_declaration(
+        &mut self,
+        function: &FunctionDeclaration,
+        interner: &Interner,
+    ) {
+        self.position = self
+            .source_map
+            .locate_next(&function_head(function, interner));
+
+        let llvm_function = self.declare_function(function, interner);
+        if unsafe { LLVMCountBasicBlocks(llvm_function) } > 0 {
+            self.unsupported("redeclaring a function in the same scope");
+            return;
+        }
+
+        if self.annex_b && !self.scopes.in_function_scope() {
+            self.scopes
+                .declare_annex_b_function(function.name().sym(), llvm_function);
+        }
+
+        // Parameters follow the environment, if the function takes one.
+        let first_param = unsafe { LLVMCountParams(llvm_function) } as usize
+            - function.parameters().as_ref().len();
+
+        let previous_block = unsafe {
+            let previous_block = LLVMGetInsertBlock(self.context.builder);
+            let entry = LLVMAppendBasicBlockInContext(
+                self.context.context,
+                llvm_function,
+                c"entry".as_ptr(),
+            );
+            LLVMPositionBuilderAtEnd(self.context.builder, entry);
+
+            previous_block
+        };
+
+        self.scopes.push_function();
+        self.environments.push(Environment::new(llvm_function));
+        let jump_targets = std::mem::take(&mut self.jump_targets);
+
+        // Parameters are copied into their own slots so they can be assigned
+        // like any other local.
+        for (index, parameter) in function.parameters().as_ref().iter().enumerate() {
+            let identifier = match parameter.variable().binding() {
+                Binding::Identifier(identifier) => *identifier,
+                Binding::Pattern(_) => {
+                    self.unsupported("destructuring parameters");
+                    continue;
+                }
+            };
+            if parameter.is_rest_param() {
+                self.unsupported("rest parameters");
+            }
+            if parameter.variable().init().is_some() {
+                self.unsupported("default parameter values");
+            }
+
+            let name = interner.resolve_expect(identifier.sym()).utf8().unwrap();
+            let ty = self.context.number_type();
+            let slot = self.context.build_entry_alloca(ty, name);
+
+            unsafe {
+                LLVMBuildStore(
+                    self.context.builder,
+                    LLVMGetParam(llvm_function, (first_param + index) as u32),
+                    slot,
+                );
+            }
+
+            let local = Local {
+                slot,
+                ty,
+                kind: BindingKind::Var,
+            };
+            self.scopes.declare_var(identifier.sym(), local);
+        }
+
+        let statements = function.body().statements();
+        self.declare_vars(statements, interner);
+        self.declare_functions(statements, interner);
+        for item in statements {
+            self.compile_statement_list_item(item, interner);
+        }
+        self.compile_function_bodies(statements, interner);
+
+        unsafe {
+            // Falling off the end of a function returns undefined.
+            if LLVMGetBasicBlockTerminator(LLVMGetInsertBlock(self.context.builder)).is_null() {
+                LLVMBuildRet(self.context.builder, self.context.create_undefined());
+            }
+
+            LLVMPositionBuilderAtEnd(self.context.builder, previous_block);
+        }
+
+        self.scopes.pop();
+        self.environments.pop();
+        self.jump_targets = jump_targets;
+
+        if self.verify_each {
+            self.verify_function(llvm_function)
+                .unwrap_or_else(|err| panic!("Codegen invariant violated: {}", err));
+        }
+    }
+
+    // The environment record of the function `depth` functions deep, as seen
+    // from the function being compiled. Nested functions follow the chain of
+    // enclosing environments out to it.
+    fn build_environment(&mut self, depth: usize) -> LLVMValueRef {
+        let current = self.environments.len() - 1;
+        if depth == current {
+            return self.environment_record(current);
+        }
+
+        unsafe {
+            let pointer_type = LLVMPointerTypeInContext(self.context.context, 0);
+            let mut record = LLVMGetParam(self.environments[current].function, 0);
+
+            for _ in depth + 1..current {
+                record =
+                    LLVMBuildLoad2(self.context.builder, pointer_type, record, c"env".as_ptr());
+            }
+
+            record
+        }
+    }
+
+    // Creates the function's record the first time something needs it. It
+    // starts out holding just the enclosing environment and grows as locals
+    // get captured.
+    fn environment_record(&mut self, depth: usize) -> LLVMValueRef {
+        if let Some(record) = self.environments[depth].record {
+            return record;
+        }
+
+        let function = self.environments[depth].function;
+        let record = unsafe {
+            let pointer_type = LLVMPointerTypeInContext(self.context.context, 0);
+            let builder = self.prologue_builder(function);
+
+            let record = LLVMBuildArrayAlloca(
+                builder,
+                pointer_type,
+                LLVMConstInt(LLVMInt32TypeInContext(self.context.context), 1, 0),
+                c"env.record".as_ptr(),
+            );
+            // Top-level functions aren't given an environment, since the
+            // top-level locals they use are globals.
+            let enclosing = if depth <= 1 {
+                LLVMConstNull(pointer_type)
+            } else {
+                LLVMGetParam(function, 0)
+            };
+            LLVMBuildStore(builder, enclosing, record);
+            LLVMDisposeBuilder(builder);
+
+            record
+        };
+
+        self.environments[depth].record = Some(record);
+
+        record
+    }
+
+    // Loads a pointer to a slot of the function `depth` functions deep out of
+    // its environment, adding the slot to the environment if it isn't there
+    // yet. Top-level locals are turned into globals instead, since exported
+    // functions can be called after top-level code has returned.
+    fn build_captured_slot(&mut self, slot: LLVMValueRef, depth: usize) -> LLVMValueRef {
+        if depth == 0 {
+            return self.promote_to_global(slot);
+        }
+
+        let record = self.environment_record(depth);
+        let function = self.environments[depth].function;
+        let captured = &mut self.environments[depth].captured;
+
+        let index = match captured.iter().position(|&captured| captured == slot) {
+            Some(position) => position + 1,
+            None => unsafe {
+                captured.push(slot);
+                let index = captured.len();
+
+                let int_type = LLVMInt32TypeInContext(self.context.context);
+                LLVMSetOperand(record, 0, LLVMConstInt(int_type, index as u64 + 1, 0));
+
+                let builder = self.prologue_builder(function);
+                let mut indices = [LLVMConstInt(int_type, index as u64, 0)];
+                let element = LLVMBuildGEP2(
+                    builder,
+                    LLVMPointerTypeInContext(self.context.context, 0),
+                    record,
+                    indices.as_mut_ptr(),
+                    1,
+                    c"".as_ptr(),
+                );
+                LLVMBuildStore(builder, slot, element);
+                LLVMDisposeBuilder(builder);
+
+                index
+            },
+        };
+
+        let record = self.build_environment(depth);
+
+        unsafe {
+            let pointer_type = LLVMPointerTypeInContext(self.context.context, 0);
+            let mut indices = [LLVMConstInt(
+                LLVMInt32TypeInContext(self.context.context),
+                index as u64,
+                0,
+            )];
+            let element = LLVMBuildGEP2(
+                self.context.builder,
+                pointer_type,
+                record,
+                indices.as_mut_ptr(),
+                1,
+                c"".as_ptr(),
+            );
+
+            LLVMBuildLoad2(
+                self.context.builder,
+                pointer_type,
+                element,
+                c"captured".as_ptr(),
+            )
+        }
+    }
+
+    fn promote_to_global(&mut self, slot: LLVMValueRef) -> LLVMValueRef {
+        unsafe {
+            if !LLVMIsAGlobalVariable(slot).is_null() {
+                return slot;
+            }
+
+            let mut length = 0;
+            let name = LLVMGetValueName2(slot, &mut length);
+            let name = CString::new(std::slice::from_raw_parts(name as *const u8, length)).unwrap();
+
+            let ty = LLVMGetAllocatedType(slot);
+            let global = LLVMAddGlobal(self.context.module, ty, name.as_ptr());
+            LLVMSetInitializer(global, LLVMConstNull(ty));
+            LLVMSetLinkage(global, LLVMLinkage::LLVMInternalLinkage);
+
+            LLVMReplaceAllUsesWith(slot, global);
+            LLVMInstructionEraseFromParent(slot);
+            self.scopes.replace_slot(slot, global);
+
+            global
+        }
+    }
+
+    // A builder placed after the allocas at the start of a function, where
+    // the environment record is filled in before any code can call a nested
+    // function.
+    fn prologue_builder(&self, function: LLVMValueRef) -> LLVMBuilderRef {
+        unsafe {
+            let entry = LLVMGetEntryBasicBlock(function);
+            let builder = LLVMCreateBuilderInContext(self.context.context);
+
+            let mut instruction = LLVMGetFirstInstruction(entry);
+            while !instruction.is_null() && !LLVMIsAAllocaInst(instruction).is_null() {
+                instruction = LLVMGetNextInstruction(instruction);
+            }
+
+            if instruction.is_null() {
+                LLVMPositionBuilderAtEnd(builder, entry);
+            } else {
+                LLVMPositionBuilderBefore(builder, instruction);
+            }
+
+            builder
+        }
+    }
+
+    // Finds the local a name refers to. Locals of an enclosing function are
+    // reached through the environment, so their slot is a pointer loaded from
+    // it.
+    fn lookup_local(&mut self, name: Sym) -> Option<Local> {
+        if let Some(local) = self.scopes.lookup(name) {
+            return Some(*local);
+        }
+
+        let (local, depth) = self.scopes.lookup_captured(name)?;
+
+        Some(Local {
+            slot: self.build_captured_slot(local.slot, depth),
+            ..local
+        })
+    }
+
+    pub fn compile_statement_list_item(
+        &mut self,
+        item: &StatementListItem,
+        interner: &Interner,
+    ) -> Option<LLVMValueRef> {
+        match item {
+            StatementListItem::Statement(statement) => self.compile_statement(statement, interner),
+            StatementListItem::Declaration(declaration) => {
+                self.compile_declaration(declaration, interner)
+            }
+        }
+    }
+
+    pub fn compile_declaration(
+        &mut self,
+        declaration: &Declaration,
+        interner: &Interner,
+    ) -> Option<LLVMValueRef> {
+        match declaration {
+            Declaration::Lexical(declaration) => {
+                self.position = self
+                    .source_map
+                    .locate_next(&declaration.to_interned_string(interner));
+                self.compile_lexical_declarations(declaration, interner);
+
+                None
+            }
+            // The body is compiled by `compile_function_bodies` once the rest
+            // of the statement list has been.
+            Declaration::FunctionDeclaration(_) => None,
+            Declaration::GeneratorDeclaration(_) => Some(self.unsupported("generators")),
+            Declaration::AsyncFunctionDeclaration(_) => Some(self.unsupported("async functions")),
+            Declaration::AsyncGeneratorDeclaration(_) => Some(self.unsupported("async generators")),
+            Declaration::ClassDeclaration(_) => Some(self.unsupported("classes")),
+        }
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    pub fn compile_expression(
+        &mut self,
+        expression: &Expression,
+        interner: &Interner,
+    ) -> Option<LLVMValueRef> {
+        match expression {
+            Expression::This => Some(self.unsupported("`this`")),
+            Expression::Identifier(identifier) => {
+                Some(self.compile_identifier(*identifier, interner))
+            }
+            Expression::Literal(literal) => match literal {
+                boa_ast::expression::literal::Literal::String(string) => {
+                    let string_value = interner.resolve_expect(*string).utf8().unwrap();
+
+                    Some(self.context.create_string_literal(string_value))
+                }
+                // Boa has already applied separators, radix prefixes and
+                // rounding to the nearest double.
+                boa_ast::expression::literal::Literal::Num(n) => {
+                    Some(self.context.create_number_literal(*n))
+                }
+                boa_ast::expression::literal::Literal::Int(n) => {
+                    Some(self.context.create_number_literal(f64::from(*n)))
+                }
+                boa_ast::expression::literal::Literal::BigInt(_) => {
+                    Some(self.unsupported("BigInt literals"))
+                }
+                boa_ast::expression::literal::Literal::Bool(value) => Some(unsafe {
+                    LLVMConstInt(
+                        LLVMInt1TypeInContext(self.context.context),
+                        u64::from(*value),
+                        0,
+                    )
+                }),
+                boa_ast::expression::literal::Literal::Null => Some(self.unsupported("`null`")),
+                boa_ast::expression::literal::Literal::Undefined => {
+                    Some(self.context.create_undefined())
+                }
+            },
+            Expression::RegExpLiteral(_) => Some(self.unsupported("regular expressions")),
+            Expression::ArrayLiteral(_) => Some(self.unsupported("array literals")),
+            Expression::ObjectLiteral(_) => Some(self.unsupported("object literals")),
+            Expression::Spread(_) => Some(self.unsupported("spread syntax")),
+            Expression::FunctionExpression(_) => Some(self.unsupported("function expressions")),
+            Expression::ArrowFunction(_) => Some(self.unsupported("arrow functions")),
+            Expression::AsyncArrowFunction(_) => Some(self.unsupported("async arrow functions")),
+            Expression::GeneratorExpression(_) => Some(self.unsupported("generators")),
+            Expression::AsyncFunctionExpression(_) => Some(self.unsupported("async functions")),
+            Expression::AsyncGeneratorExpression(_) => Some(self.unsupported("async generators")),
+            Expression::ClassExpression(_) => Some(self.unsupported("classes")),
+            Expression::TemplateLiteral(_) => Some(self.unsupported("template literals")),
+            Expression::PropertyAccess(_) => Some(self.unsupported("property access")),
+            Expression::New(new) => {
+                if self.reject_dynamic_code(new.constructor(), "Function", interner) {
+                    return Some(self.context.create_undefined());
+                }
+
+                Some(self.unsupported("`new`"))
+            }
+            Expression::Call(call) => {
+                if let Some(value) = self.compile_builtin_call(call, interner) {
+                    return Some(value);
+                }
+
+                if self.reject_dynamic_code(call.function(), "eval", interner)
+                    || self.reject_dynamic_code(call.function(), "Function", interner)
+                {
+                    return Some(self.context.create_undefined());
+                }
+
+                if let Some(value) = self.compile_user_call(call, interner) {
+                    return Some(value);
+                }
+
+                let identifier = match call.function() {
+                    Expression::Identifier(ident) => {
+                        interner.resolve_expect(ident.sym()).utf8().unwrap()
+                    }
+                    _ => return Some(self.unsupported("calls to non-identifier callees")),
+                };
+
+                let mut args = vec![];
+                for arg in call.args() {
+                    args.push(self.compile_expression(arg, interner).unwrap());
+                }
+
+                let mut arg_types = vec![];
+
+                for arg in &args {
+                    arg_types.push(unsafe { LLVMTypeOf(*arg) });
+                }
+
+                let function = unsafe {
+                    let c_name = CString::new(identifier).unwrap();
+                    let function = LLVMGetNamedFunction(self.context.module, c_name.as_ptr());
+
+                    let function_type = LLVMFunctionType(
+                        LLVMInt32TypeInContext(self.context.context),
+                        arg_types.as_mut_ptr(),
+                        arg_types.len() as u32,
+                        0,
+                    );
+
+                    if function.is_null() {
+                        let function =
+                            LLVMAddFunction(self.context.module, c_name.as_ptr(), function_type);
+
+                        LLVMSetLinkage(function, LLVMLinkage::LLVMExternalLinkage);
+
+                        (function, function_type)
+                    } else {
+                        (function, function_type)
+                    }
+                };
+
+                Some(unsafe {
+                    LLVMBuildCall2(
+                        self.context.builder,
+                        function.1,
+                        function.0,
+                        args.as_mut_ptr(),
+                        args.len() as u32,
+                        b"\0".as_ptr(),
+                    )
+                })
+            }
+            Expression::SuperCall(_) => Some(self.unsupported("`super` calls")),
+            Expression::ImportCall(_) => Some(self.unsupported("dynamic imports")),
+            Expression::Optional(_) => Some(self.unsupported("optional chaining")),
+            Expression::TaggedTemplate(_) => Some(self.unsupported("tagged templates")),
+            Expression::NewTarget => Some(self.unsupported("`new.target`")),
+            Expression::ImportMeta => Some(self.unsupported("`import.meta`")),
+            Expression::Assign(assign) => Some(self.compile_assign(assign, interner)),
+            Expression::Unary(unary) => Some(self.compile_unary(unary, interner)),
+            Expression::Update(update) => Some(self.compile_update(update, interner)),
+            Expression::Binary(binary) => Some(self.compile_binary(binary, interner)),
+            Expression::BinaryInPrivate(_) => Some(self.unsupported("private `in` checks")),
+            Expression::Conditional(conditional) => {
+                let condition = self
+                    .compile_expression(conditional.condition(), interner)
+                    .unwrap();
+                let condition = self.context.build_to_boolean(condition);
+
+                let then_block = self.append_block(c"cond.then");
+                let else_block = self.append_block(c"cond.else");
+                let end_block = self.append_block(c"cond.end");
+                unsafe {
+                    LLVMBuildCondBr(self.context.builder, condition, then_block, else_block);
+                    LLVMPositionBuilderAtEnd(self.context.builder, then_block);
+                }
+
+                // Each arm may have added blocks of its own, so the phi's
+                // incoming blocks are wherever the arms ended up.
+                let if_true = self
+                    .compile_expression(conditional.if_true(), interner)
+                    .unwrap();
+                let then_block = unsafe { LLVMGetInsertBlock(self.context.builder) };
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, else_block);
+                }
+                let if_false = self
+                    .compile_expression(conditional.if_false(), interner)
+                    .unwrap();
+                let else_block = unsafe { LLVMGetInsertBlock(self.context.builder) };
+
+                Some(self.build_merge(
+                    (if_true, then_block),
+                    (if_false, else_block),
+                    end_block,
+                    "conditional expressions with arms of different types",
+                ))
+            }
+            Expression::Await(_) => Some(self.unsupported("`await`")),
+            Expression::Yield(_) => Some(self.unsupported("`yield`")),
+            Expression::Parenthesized(parenthesized) => {
+                self.compile_expression(parenthesized.expression(), interner)
+            }
+            _ => Some(self.unsupported("this expression")),
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub fn finish_root_function(&mut self) -> Result<(), String> {
+        unsafe {
+            LLVMBuildRet(
+                self.context.builder,
+                LLVMConstInt(LLVMInt32TypeInContext(self.context.context), 0, 0),
+            );
+        }
+
+        if self.verify_each {
+            self.verify_function(self.context.root_function)?;
+        }
+
+        // `inline` annotations are honoured even without optimizations, by
+        // running the inliner for `alwaysinline` functions. From -O1 on,
+        // locals are promoted to registers, number conversions that cancel
+        // out are folded, and dead stores are removed.
+        let mut passes = vec![];
+        if self
+            .annotations
+            .iter()
+            .any(|(_, annotation)| *annotation == Annotation::Inline)
+        {
+            passes.push("always-inline");
+        }
+        if self.opt_level > 0 {
+            passes.push("function(mem2reg,instcombine,dse)");
+        }
+
+        if !passes.is_empty() {
+            let passes = CString::new(passes.join(",")).unwrap();
+
+            unsafe {
+                let options = LLVMCreatePassBuilderOptions();
+                let error = LLVMRunPasses(
+                    self.context.module,
+                    passes.as_ptr(),
+                    std::ptr::null_mut(),
+                    options,
+                );
+                LLVMDisposePassBuilderOptions(options);
+
+                if !error.is_null() {
+                    let message = LLVMGetErrorMessage(error);
+                    let err = CStr::from_ptr(message).to_string_lossy().into_owned();
+                    LLVMDisposeErrorMessage(message);
+
+                    return Err(format!("Failed to optimize the module: {}", err));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Between statements the builder must sit at the end of a block that can
+    // still take instructions, otherwise the next statement is emitted into
+    // nowhere or after a terminator.
+    fn check_builder_position(&self) -> Result<(), String> {
+        unsafe {
+            let block = LLVMGetInsertBlock(self.context.builder);
+
+            if block.is_null() {
+                return Err("builder is not positioned at a block".to_string());
+            }
+
+            if !LLVMGetBasicBlockTerminator(block).is_null() {
+                return Err(format!(
+                    "builder is positioned after the terminator of {}",
+                    block_name(block)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Code after a terminator like `return` is dead but still has to go
+    // somewhere, so it gets a block of its own with no predecessors.
+    fn start_unreachable_block(&self) {
+        let block = self.append_block(c"unreachable");
+
+        unsafe {
+            LLVMPositionBuilderAtEnd(self.context.builder, block);
+        }
+    }
+
+    // Dispatches with an LLVM switch on the value as an integer. Values that
+    // aren't integers can't match any of the cases and go to the default.
+    fn build_switch_dispatch(
+        &self,
+        value: LLVMValueRef,
+        cases: &[(i64, LLVMBasicBlockRef)],
+        default_block: LLVMBasicBlockRef,
+    ) {
+        unsafe {
+            let int_type = LLVMInt64TypeInContext(self.context.context);
+            let int_value = self.context.build_saturating_to_i64(value);
+            let round_trip = LLVMBuildSIToFP(
+                self.context.builder,
+                int_value,
+                self.context.number_type(),
+                c"".as_ptr(),
+            );
+            let is_integer = self.context.build_strict_equals(round_trip, value);
+
+            let dispatch_block = self.append_block(c"switch.dispatch");
+            LLVMBuildCondBr(
+                self.context.builder,
+                is_integer,
+                dispatch_block,
+                default_block,
+            );
+            LLVMPositionBuilderAtEnd(self.context.builder, dispatch_block);
+
+            let switch = LLVMBuildSwitch(
+                self.context.builder,
+                int_value,
+                default_block,
+                cases.len() as u32,
+            );
+            let mut seen = std::collections::HashSet::new();
+            for (case_value, block) in cases {
+                // The first of several equal cases wins, and LLVM rejects
+                // duplicates anyway.
+                if seen.insert(*case_value) {
+                    LLVMAddCase(
+                        switch,
+                        LLVMConstInt(int_type, *case_value as u64, 1),
+                        *block,
+                    );
+                }
+            }
+        }
+    }
+
+    fn compile_loop_body(
+        &mut self,
+        body: &Statement,
+        labels: Vec<Sym>,
+        break_block: LLVMBasicBlockRef,
+        continue_block: LLVMBasicBlockRef,
+        interner: &Interner,
+    ) {
+        self.jump_targets.push(JumpTarget {
+            labels,
+            kind: JumpKind::Loop { continue_block },
+            break_block,
+        });
+        self.compile_statement(body, interner);
+        self.jump_targets.pop();
+    }
+
+    fn build_jump(
+        &mut self,
+        target: Option<LLVMBasicBlockRef>,
+        label: Option<Sym>,
+        statement: &str,
+        interner: &Interner,
+    ) {
+        let Some(target) = target else {
+            match label {
+                Some(label) => self.error(format!(
+                    "Undefined label `{}` in {} statement",
+                    interner.resolve_expect(label).utf8().unwrap(),
+                    statement
+                )),
+                None => self.error(format!("Illegal {} statement outside of a loop", statement)),
+            }
+
+            return;
+        };
+
+        unsafe {
+            LLVMBuildBr(self.context.builder, target);
+        }
+        self.start_unreachable_block();
+    }
+
+    fn build_loop_condition(
+        &mut self,
+        condition: &Expression,
+        body_block: LLVMBasicBlockRef,
+        end_block: LLVMBasicBlockRef,
+        interner: &Interner,
+    ) {
+        let condition = self.compile_expression(condition, interner).unwrap();
+        let condition = self.context.build_to_boolean(condition);
+
+        unsafe {
+            LLVMBuildCondBr(self.context.builder, condition, body_block, end_block);
+        }
+    }
+
+    // Appends a block to the function currently being built.
+    fn append_block(&self, name: &CStr) -> LLVMBasicBlockRef {
+        unsafe {
+            let function = LLVMGetBasicBlockParent(LLVMGetInsertBlock(self.context.builder));
+
+            LLVMAppendBasicBlockInContext(self.context.context, function, name.as_ptr())
+        }
+    }
+
+    // Falls through into `block` unless the current block already ended, for
+    // example with a `return`.
+    fn branch_to(&self, block: LLVMBasicBlockRef) {
+        unsafe {
+            if LLVMGetBasicBlockTerminator(LLVMGetInsertBlock(self.context.builder)).is_null() {
+                LLVMBuildBr(self.context.builder, block);
+            }
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub fn verify_function(&self, function: LLVMValueRef) -> Result<(), String> {
+        unsafe {
+            let mut name_length = 0;
+            let name = LLVMGetValueName2(function, &mut name_length);
+            let name =
+                String::from_utf8_lossy(std::slice::from_raw_parts(name as *const u8, name_length));
+
+            let mut block = LLVMGetFirstBasicBlock(function);
+            while !block.is_null() {
+                if LLVMGetBasicBlockTerminator(block).is_null() {
+                    return Err(format!(
+                        "block {} in function {} is not terminated",
+                        block_name(block),
+                        name
+                    ));
+                }
+
+                block = LLVMGetNextBasicBlock(block);
+            }
+
+            if LLVMVerifyFunction(
+                function,
+                llvm_sys::analysis::LLVMVerifierFailureAction::LLVMPrintMessageAction,
+            ) != 0
+            {
+                return Err(format!("function {} failed IR verification", name));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_unary(&mut self, unary: &Unary, interner: &Interner) -> LLVMValueRef {
+        let value = self.compile_expression(unary.target(), interner).unwrap();
+        let builder = self.context.builder;
+
+        unsafe {
+            match unary.op() {
+                UnaryOp::Minus => {
+                    let value = self.context.build_to_number(value);
+
+                    LLVMBuildFNeg(builder, value, c"neg".as_ptr())
+                }
+                UnaryOp::Plus => self.context.build_to_number(value),
+                UnaryOp::Not => {
+                    let value = self.context.build_to_boolean(value);
+
+                    LLVMBuildNot(builder, value, c"not".as_ptr())
+                }
+                UnaryOp::Tilde => {
+                    let value = self.context.build_to_int32(value);
+                    let value = LLVMBuildNot(builder, value, c"bitnot".as_ptr());
+
+                    LLVMBuildSIToFP(builder, value, self.context.number_type(), c"".as_ptr())
+                }
+                // The operand is still evaluated for its side effects.
+                UnaryOp::Void => self.context.create_undefined(),
+                UnaryOp::TypeOf => self.unsupported("`typeof`"),
+                UnaryOp::Delete => self.unsupported("`delete`"),
+            }
+        }
+    }
+
+    fn compile_binary(&mut self, binary: &Binary, interner: &Interner) -> LLVMValueRef {
+        match binary.op() {
+            BinaryOp::Arithmetic(op) => {
+                let lhs = self.compile_expression(binary.lhs(), interner).unwrap();
+                let rhs = self.compile_expression(binary.rhs(), interner).unwrap();
+
+                self.build_arithmetic(op, lhs, rhs)
+            }
+            BinaryOp::Relational(
+                RelationalOp::Equal
+                | RelationalOp::NotEqual
+                | RelationalOp::StrictEqual
+                | RelationalOp::StrictNotEqual,
+            ) if self.is_undefined(binary.lhs(), interner)
+                || self.is_undefined(binary.rhs(), interner) =>
+            {
+                self.unsupported("comparing against `undefined`")
+            }
+            BinaryOp::Relational(op) => {
+                let lhs = self.compile_expression(binary.lhs(), interner).unwrap();
+                let rhs = self.compile_expression(binary.rhs(), interner).unwrap();
+
+                self.build_comparison(op, lhs, rhs)
+            }
+            BinaryOp::Bitwise(op) => {
+                let lhs = self.compile_expression(binary.lhs(), interner).unwrap();
+                let rhs = self.compile_expression(binary.rhs(), interner).unwrap();
+
+                self.build_bitwise(op, lhs, rhs)
+            }
+            BinaryOp::Logical(LogicalOp::Coalesce) => self.unsupported("nullish coalescing"),
+            BinaryOp::Logical(op) => {
+                let lhs = self.compile_expression(binary.lhs(), interner).unwrap();
+                let condition = self.context.build_to_boolean(lhs);
+                let lhs_block = unsafe { LLVMGetInsertBlock(self.context.builder) };
+
+                // The right operand is only evaluated when the left one
+                // doesn't decide the result, and the result is whichever
+                // operand was evaluated last.
+                let rhs_block = self.append_block(c"logical.rhs");
+                let end_block = self.append_block(c"logical.end");
+                unsafe {
+                    match op {
+                        LogicalOp::And => {
+                            LLVMBuildCondBr(self.context.builder, condition, rhs_block, end_block)
+                        }
+                        _ => LLVMBuildCondBr(self.context.builder, condition, end_block, rhs_block),
+                    };
+                    LLVMPositionBuilderAtEnd(self.context.builder, rhs_block);
+                }
+
+                let rhs = self.compile_expression(binary.rhs(), interner).unwrap();
+                let rhs_block = unsafe { LLVMGetInsertBlock(self.context.builder) };
+                self.build_merge(
+                    (lhs, lhs_block),
+                    (rhs, rhs_block),
+                    end_block,
+                    "`&&` and `||` on operands of different types",
+                )
+            }
+            // The left operand is only evaluated for its side effects.
+            BinaryOp::Comma => {
+                self.compile_expression(binary.lhs(), interner);
+
+                self.compile_expression(binary.rhs(), interner).unwrap()
+            }
+        }
+    }
+
+    // Arithmetic is done on doubles. Integer operands, like the i32 results of
+    // extern calls, are converted first so overflow and division behave as in
+    // JS.
+    fn build_arithmetic(
+        &mut self,
+        op: ArithmeticOp,
+        lhs: LLVMValueRef,
+        rhs: LLVMValueRef,
+    ) -> LLVMValueRef {
+        let is_string = |value| unsafe {
+            LLVMGetTypeKind(LLVMTypeOf(value)) == LLVMTypeKind::LLVMPointerTypeKind
+        };
+        if op == ArithmeticOp::Add && (is_string(lhs) || is_string(rhs)) {
+            return self.unsupported("string concatenation");
+        }
+
+        let lhs = self.context.build_to_number(lhs);
+        let rhs = self.context.build_to_number(rhs);
+        let builder = self.context.builder;
+
+        unsafe {
+            match op {
+                ArithmeticOp::Add => LLVMBuildFAdd(builder, lhs, rhs, c"add".as_ptr()),
+                ArithmeticOp::Sub => LLVMBuildFSub(builder, lhs, rhs, c"sub".as_ptr()),
+                ArithmeticOp::Mul => LLVMBuildFMul(builder, lhs, rhs, c"mul".as_ptr()),
+                ArithmeticOp::Div => LLVMBuildFDiv(builder, lhs, rhs, c"div".as_ptr()),
+                // frem follows fmod, which keeps the sign of the dividend like
+                // JS `%` does.
+                ArithmeticOp::Mod => LLVMBuildFRem(builder, lhs, rhs, c"rem".as_ptr()),
+                ArithmeticOp::Exp => self.unsupported("exponentiation"),
+            }
+        }
+    }
+
+    // Joins two values coming from different blocks that both branch, or are
+    // about to branch, to `end_block`, and leaves the builder there. The
+    // result is one of the values as it is, so values of different types can
+    // only be merged if both are numbers. Otherwise the merge is reported as
+    // `feature`.
+    fn build_merge(
+        &mut self,
+        (mut lhs, lhs_block): (LLVMValueRef, LLVMBasicBlockRef),
+        (mut rhs, rhs_block): (LLVMValueRef, LLVMBasicBlockRef),
+        end_block: LLVMBasicBlockRef,
+        feature: &str,
+    ) -> LLVMValueRef {
+        unsafe {
+            let builder = self.context.builder;
+            let (lhs_type, rhs_type) = (LLVMTypeOf(lhs), LLVMTypeOf(rhs));
+
+            if lhs_type != rhs_type && (!is_number_type(lhs_type) || !is_number_type(rhs_type)) {
+                LLVMPositionBuilderAtEnd(builder, rhs_block);
+                self.branch_to(end_block);
+                LLVMPositionBuilderAtEnd(builder, lhs_block);
+                self.branch_to(end_block);
+                LLVMPositionBuilderAtEnd(builder, end_block);
+
+                return self.unsupported(feature);
+            }
+
+            if lhs_type != rhs_type {
+                let terminator = LLVMGetBasicBlockTerminator(lhs_block);
+                if terminator.is_null() {
+                    LLVMPositionBuilderAtEnd(builder, lhs_block);
+                } else {
+                    LLVMPositionBuilderBefore(builder, terminator);
+                }
+                lhs = self.context.build_to_number(lhs);
+
+                LLVMPositionBuilderAtEnd(builder, rhs_block);
+                rhs = self.context.build_to_number(rhs);
+            }
+
+            LLVMPositionBuilderAtEnd(builder, rhs_block);
+            self.branch_to(end_block);
+            LLVMPositionBuilderAtEnd(builder, lhs_block);
+            self.branch_to(end_block);
+
+            LLVMPositionBuilderAtEnd(builder, end_block);
+            let phi = LLVMBuildPhi(builder, LLVMTypeOf(lhs), c"".as_ptr());
+            let mut values = vec![lhs, rhs];
+            let mut blocks = vec![lhs_block, rhs_block];
+            LLVMAddIncoming(phi, values.as_mut_ptr(), blocks.as_mut_ptr(), 2);
+
+            phi
+        }
+    }
+
+    // Bitwise operators work on the operands' ToInt32 values. Shift counts
+    // only use their low five bits, and `>>>` produces an unsigned result.
+    fn build_bitwise(&self, op: BitwiseOp, lhs: LLVMValueRef, rhs: LLVMValueRef) -> LLVMValueRef {
+        let lhs = self.context.build_to_int32(lhs);
+        let rhs = self.context.build_to_int32(rhs);
+        let builder = self.context.builder;
+
+        unsafe {
+            let shift = || {
+                LLVMBuildAnd(
+                    builder,
+                    rhs,
+                    LLVMConstInt(LLVMTypeOf(rhs), 31, 0),
+                    c"".as_ptr(),
+                )
+            };
+            let value = match op {
+                BitwiseOp::And => LLVMBuildAnd(builder, lhs, rhs, c"and".as_ptr()),
+                BitwiseOp::Or => LLVMBuildOr(builder, lhs, rhs, c"or".as_ptr()),
+                BitwiseOp::Xor => LLVMBuildXor(builder, lhs, rhs, c"xor".as_ptr()),
+                BitwiseOp::Shl => LLVMBuildShl(builder, lhs, shift(), c"shl".as_ptr()),
+                BitwiseOp::Shr => LLVMBuildAShr(builder, lhs, shift(), c"shr".as_ptr()),
+                BitwiseOp::UShr => {
+                    let value = LLVMBuildLShr(builder, lhs, shift(), c"ushr".as_ptr());
+
+                    return LLVMBuildUIToFP(
+                        builder,
+                        value,
+                        self.context.number_type(),
+                        c"".as_ptr(),
+                    );
+                }
+            };
+
+            LLVMBuildSIToFP(builder, value, self.context.number_type(), c"".as_ptr())
+        }
+    }
+
+    // Comparisons produce i1 booleans. Two strings compare by their bytes,
+    // two booleans compare directly for equality, and anything else is
+    // compared as numbers, which is what loose equality and the relational
+    // operators do for the types there are so far.
+    fn build_comparison(
+        &mut self,
+        op: RelationalOp,
+        lhs: LLVMValueRef,
+        rhs: LLVMValueRef,
+    ) -> LLVMValueRef {
+        use llvm_sys::LLVMIntPredicate::*;
+        use llvm_sys::LLVMRealPredicate::*;
+
+        let builder = self.context.builder;
+
+        let (int_predicate, real_predicate) = match op {
+            RelationalOp::StrictEqual => return self.context.build_strict_equals(lhs, rhs),
+            RelationalOp::StrictNotEqual => unsafe {
+                let equal = self.context.build_strict_equals(lhs, rhs);
+
+                return LLVMBuildNot(builder, equal, c"ne".as_ptr());
+            },
+            RelationalOp::Equal => (LLVMIntEQ, LLVMRealOEQ),
+            // NaN is unequal to everything, itself included.
+            RelationalOp::NotEqual => (LLVMIntNE, LLVMRealUNE),
+            RelationalOp::LessThan => (LLVMIntSLT, LLVMRealOLT),
+            RelationalOp::LessThanOrEqual => (LLVMIntSLE, LLVMRealOLE),
+            RelationalOp::GreaterThan => (LLVMIntSGT, LLVMRealOGT),
+            RelationalOp::GreaterThanOrEqual => (LLVMIntSGE, LLVMRealOGE),
+            RelationalOp::In => return self.unsupported("`in`"),
+            RelationalOp::InstanceOf => return self.unsupported("`instanceof`"),
+        };
+
+        unsafe {
+            let lhs_kind = LLVMGetTypeKind(LLVMTypeOf(lhs));
+            let rhs_kind = LLVMGetTypeKind(LLVMTypeOf(rhs));
+
+            if lhs_kind == LLVMTypeKind::LLVMPointerTypeKind
+                && rhs_kind == LLVMTypeKind::LLVMPointerTypeKind
+            {
+                let order = self.context.build_strcmp(lhs, rhs);
+
+                return LLVMBuildICmp(
+                    builder,
+                    int_predicate,
+                    order,
+                    LLVMConstNull(LLVMTypeOf(order)),
+                    c"cmp".as_ptr(),
+                );
+            }
+
+            if LLVMTypeOf(lhs) == LLVMTypeOf(rhs)
+                && lhs_kind == LLVMTypeKind::LLVMIntegerTypeKind
+                && matches!(op, RelationalOp::Equal | RelationalOp::NotEqual)
+            {
+                return LLVMBuildICmp(builder, int_predicate, lhs, rhs, c"cmp".as_ptr());
+            }
+
+            let lhs = self.context.build_to_number(lhs);
+            let rhs = self.context.build_to_number(rhs);
+
+            LLVMBuildFCmp(builder, real_predicate, lhs, rhs, c"cmp".as_ptr())
+        }
+    }
+
+    // Whether an expression is known to be `undefined` without compiling it.
+    fn is_undefined(&self, expression: &Expression, interner: &Interner) -> bool {
+        match expression {
+            Expression::Literal(Literal::Undefined) => true,
+            Expression::Identifier(identifier) => {
+                interner.resolve_expect(identifier.sym()).utf8() == Some("undefined")
+                    && self.scopes.lookup(identifier.sym()).is_none()
+                    && self.scopes.lookup_captured(identifier.sym()).is_none()
+            }
+            Expression::Unary(unary) => unary.op() == UnaryOp::Void,
+            Expression::Parenthesized(parenthesized) => {
+                self.is_undefined(parenthesized.expression(), interner)
+            }
+            _ => false,
+        }
+    }
+
+    // The type an expression compiles to, where that's known without
+    // compiling it. `undefined` has no type of its own.
+    fn static_type(&self, expression: &Expression, interner: &Interner) -> Option<LLVMTypeRef> {
+        let number = self.context.number_type();
+        let (boolean, string) = unsafe {
+            (
+                LLVMInt1TypeInContext(self.context.context),
+                LLVMPointerTypeInContext(self.context.context, 0),
+            )
+        };
+        // Values merged from two branches keep their type if both agree.
+        let merged = |lhs: &Expression, rhs: &Expression| {
+            let (lhs, rhs) = (
+                self.static_type(lhs, interner)?,
+                self.static_type(rhs, interner)?,
+            );
+
+            if lhs == rhs {
+                Some(lhs)
+            } else if is_number_type(lhs) && is_number_type(rhs) {
+                Some(number)
+            } else {
+                None
+            }
+        };
+
+        match expression {
+            Expression::Literal(Literal::String(_)) => Some(string),
+            Expression::Literal(Literal::Num(_) | Literal::Int(_)) => Some(number),
+            Expression::Literal(Literal::Bool(_)) => Some(boolean),
+            Expression::Identifier(identifier) => {
+                let sym = identifier.sym();
+                let local = self
+                    .scopes
+                    .lookup(sym)
+                    .copied()
+                    .or_else(|| Some(self.scopes.lookup_captured(sym)?.0));
+
+                match local {
+                    Some(local) => Some(local.ty),
+                    None => match interner.resolve_expect(sym).utf8() {
+                        Some("NaN" | "Infinity") => Some(number),
+                        _ => None,
+                    },
+                }
+            }
+            Expression::Unary(unary) => match unary.op() {
+                UnaryOp::Not => Some(boolean),
+                UnaryOp::Minus | UnaryOp::Plus | UnaryOp::Tilde => Some(number),
+                UnaryOp::Void | UnaryOp::TypeOf | UnaryOp::Delete => None,
+            },
+            Expression::Update(_) => Some(number),
+            Expression::Binary(binary) => match binary.op() {
+                BinaryOp::Arithmetic(_) | BinaryOp::Bitwise(_) => Some(number),
+                BinaryOp::Relational(RelationalOp::In | RelationalOp::InstanceOf) => None,
+                BinaryOp::Relational(_) => Some(boolean),
+                BinaryOp::Logical(LogicalOp::Coalesce) => None,
+                BinaryOp::Logical(_) => merged(binary.lhs(), binary.rhs()),
+                BinaryOp::Comma => self.static_type(binary.rhs(), interner),
+            },
+            Expression::Conditional(conditional) => {
+                merged(conditional.if_true(), conditional.if_false())
+            }
+            Expression::Assign(assign) if matches!(assign.op(), AssignOp::Assign) => {
+                self.static_type(assign.rhs(), interner)
+            }
+            Expression::Parenthesized(parenthesized) => {
+                self.static_type(parenthesized.expression(), interner)
+            }
+            // User functions return numbers and C functions return integers,
+            // while `__jscc` builtins produce bytes.
+            Expression::Call(call) => match call.function() {
+                Expression::Identifier(_) => Some(number),
+                Expression::PropertyAccess(PropertyAccess::Simple(access))
+                    if matches!(
+                        access.target(),
+                        Expression::Identifier(ident)
+                            if interner.resolve_expect(ident.sym()).utf8() == Some("__jscc")
+                    ) =>
+                {
+                    Some(string)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn compile_identifier(&mut self, identifier: Identifier, interner: &Interner) -> LLVMValueRef {
+        if let Some(local) = self.lookup_local(identifier.sym()) {
+            let name =
+                CString::new(interner.resolve_expect(identifier.sym()).utf8().unwrap()).unwrap();
+
+            return unsafe {
+                LLVMBuildLoad2(self.context.builder, local.ty, local.slot, name.as_ptr())
+            };
+        }
+
+        match interner.resolve_expect(identifier.sym()).utf8().unwrap() {
+            "undefined" => self.context.create_undefined(),
+            "NaN" => self.context.create_number_literal(f64::NAN),
+            "Infinity" => self.context.create_number_literal(f64::INFINITY),
+            name => {
+                self.error(format!("`{}` is not defined", name));
+
+                self.context.create_undefined()
+            }
+        }
+    }
+
+    // Resolves the local an assignment to `identifier` stores into, reporting
+    // targets that can't be assigned.
+    fn assignment_target(&mut self, identifier: Identifier, interner: &Interner) -> Option<Local> {
+        let name = interner.resolve_expect(identifier.sym()).utf8().unwrap();
+
+        match self.lookup_local(identifier.sym()) {
+            Some(local) if local.kind == BindingKind::Const => {
+                self.error(format!(
+                    "Cannot assign to `{}` because it is a constant",
+                    name
+                ));
+
+                None
+            }
+            Some(local) => Some(local),
+            None => {
+                self.error(format!("Cannot assign to undeclared variable `{}`", name));
+
+                None
+            }
+        }
+    }
+
+    fn compile_assign(&mut self, assign: &Assign, interner: &Interner) -> LLVMValueRef {
+        let identifier = match assign.lhs() {
+            AssignTarget::Identifier(identifier) => *identifier,
+            AssignTarget::Access(_) => return self.unsupported("property access"),
+            AssignTarget::Pattern(_) => return self.unsupported("destructuring assignment"),
+        };
+
+        match assign.op() {
+            AssignOp::Assign => {
+                let Some(local) = self.assignment_target(identifier, interner) else {
+                    // The right side is still compiled so errors in it get
+                    // reported too.
+                    self.compile_expression(assign.rhs(), interner);
+
+                    return self.context.create_undefined();
+                };
+                let value = self.compile_expression(assign.rhs(), interner).unwrap();
+
+                self.store_local(local, value)
+            }
+            AssignOp::BoolAnd => {
+                self.compile_logical_assign(LogicalOp::And, identifier, assign.rhs(), interner)
+            }
+            AssignOp::BoolOr => {
+                self.compile_logical_assign(LogicalOp::Or, identifier, assign.rhs(), interner)
+            }
+            AssignOp::Coalesce => self.unsupported("nullish coalescing"),
+            op => {
+                let Some(local) = self.assignment_target(identifier, interner) else {
+                    return self.context.create_undefined();
+                };
+
+                // `x op= y` is `x = x op y` with `x` evaluated once.
+                let current = unsafe {
+                    LLVMBuildLoad2(self.context.builder, local.ty, local.slot, c"".as_ptr())
+                };
+                let rhs = self.compile_expression(assign.rhs(), interner).unwrap();
+                let value = match op {
+                    AssignOp::Add => self.build_arithmetic(ArithmeticOp::Add, current, rhs),
+                    AssignOp::Sub => self.build_arithmetic(ArithmeticOp::Sub, current, rhs),
+                    AssignOp::Mul => self.build_arithmetic(ArithmeticOp::Mul, current, rhs),
+                    AssignOp::Div => self.build_arithmetic(ArithmeticOp::Div, current, rhs),
+                    AssignOp::Mod => self.build_arithmetic(ArithmeticOp::Mod, current, rhs),
+                    AssignOp::Exp => self.build_arithmetic(ArithmeticOp::Exp, current, rhs),
+                    AssignOp::And => self.build_bitwise(BitwiseOp::And, current, rhs),
+                    AssignOp::Or => self.build_bitwise(BitwiseOp::Or, current, rhs),
+                    AssignOp::Xor => self.build_bitwise(BitwiseOp::Xor, current, rhs),
+                    AssignOp::Shl => self.build_bitwise(BitwiseOp::Shl, current, rhs),
+                    AssignOp::Shr => self.build_bitwise(BitwiseOp::Shr, current, rhs),
+                    AssignOp::Ushr => self.build_bitwise(BitwiseOp::UShr, current, rhs),
+                    AssignOp::Assign
+                    | AssignOp::BoolAnd
+                    | AssignOp::BoolOr
+                    | AssignOp::Coalesce => {
+                        unreachable!()
+                    }
+                };
+
+                self.store_local(local, value)
+            }
+        }
+    }
+
+    // `x &&= y` and `x ||= y` only evaluate and store `y` when `x` doesn't
+    // already decide the result.
+    fn compile_logical_assign(
+        &mut self,
+        op: LogicalOp,
+        identifier: Identifier,
+        rhs: &Expression,
+        interner: &Interner,
+    ) -> LLVMValueRef {
+        let Some(local) = self.assignment_target(identifier, interner) else {
+            return self.context.create_undefined();
+        };
+
+        let current =
+            unsafe { LLVMBuildLoad2(self.context.builder, local.ty, local.slot, c"".as_ptr()) };
+        let condition = self.context.build_to_boolean(current);
+        let current_block = unsafe { LLVMGetInsertBlock(self.context.builder) };
+
+        let assign_block = self.append_block(c"assign.rhs");
+        let end_block = self.append_block(c"assign.end");
+        unsafe {
+            match op {
+                LogicalOp::And => {
+                    LLVMBuildCondBr(self.context.builder, condition, assign_block, end_block)
+                }
+                _ => LLVMBuildCondBr(self.context.builder, condition, end_block, assign_block),
+            };
+            LLVMPositionBuilderAtEnd(self.context.builder, assign_block);
+        }
+
+        let value = self.compile_expression(rhs, interner).unwrap();
+        let stored = self.store_local(local, value);
+        // A value of another type has already been reported, and the variable
+        // keeps its current value.
+        let value = if unsafe { LLVMTypeOf(stored) } == local.ty {
+            stored
+        } else {
+            current
+        };
+        let assign_block = unsafe { LLVMGetInsertBlock(self.context.builder) };
+
+        self.build_merge(
+            (current, current_block),
+            (value, assign_block),
+            end_block,
+            "changing the type of a variable",
+        )
+    }
+
+    // Slots are statically typed, so a value of another type can't be stored
+    // in place. Integer numbers are stored in number slots as doubles.
+    fn store_local(&mut self, local: Local, mut value: LLVMValueRef) -> LLVMValueRef {
+        let ty = unsafe { LLVMTypeOf(value) };
+        if ty != local.ty && local.ty == self.context.number_type() && is_number_type(ty) {
+            value = self.context.build_to_number(value);
+        }
+
+        if unsafe { LLVMTypeOf(value) } != local.ty {
+            return self.unsupported("changing the type of a variable");
+        }
+
+        unsafe {
+            LLVMBuildStore(self.context.builder, value, local.slot);
+        }
+
+        value
+    }
+
+    fn compile_update(&mut self, update: &Update, interner: &Interner) -> LLVMValueRef {
+        let identifier = match update.target() {
+            UpdateTarget::Identifier(identifier) => *identifier,
+            UpdateTarget::PropertyAccess(_) => return self.unsupported("property access"),
+        };
+        let Some(local) = self.assignment_target(identifier, interner) else {
+            return self.context.create_undefined();
+        };
+        if local.ty != self.context.number_type() {
+            return self.unsupported("changing the type of a variable");
+        }
+
+        let builder = self.context.builder;
+        let one = self.context.create_number_literal(1.0);
+
+        unsafe {
+            let old_value = LLVMBuildLoad2(builder, local.ty, local.slot, c"".as_ptr());
+            let new_value = match update.op() {
+                UpdateOp::IncrementPost | UpdateOp::IncrementPre => {
+                    LLVMBuildFAdd(builder, old_value, one, c"inc".as_ptr())
+                }
+                UpdateOp::DecrementPost | UpdateOp::DecrementPre => {
+                    LLVMBuildFSub(builder, old_value, one, c"dec".as_ptr())
+                }
+            };
+            LLVMBuildStore(builder, new_value, local.slot);
+
+            match update.op() {
+                UpdateOp::IncrementPost | UpdateOp::DecrementPost => old_value,
+                UpdateOp::IncrementPre | UpdateOp::DecrementPre => new_value,
+            }
+        }
+    }
+
+    fn compile_var_declaration(&mut self, variable: &Variable, interner: &Interner) {
+        let identifier = match variable.binding() {
+            Binding::Identifier(identifier) => *identifier,
+            Binding::Pattern(_) => {
+                self.unsupported("destructuring");
+                return;
+            }
+        };
+        let name = interner.resolve_expect(identifier.sym()).utf8().unwrap();
+
+        let value = match variable.init() {
+            Some(init) => self.compile_expression(init, interner).unwrap(),
+            // Redeclaring a `var` without an initializer keeps its value.
+            None if self.scopes.lookup_var(identifier.sym()).is_some() => return,
+            None => self.context.create_undefined(),
+        };
+
+        // The slot may be read on paths where this declaration didn't run, so
+        // it keeps the type it was hoisted with.
+        let local = match self.scopes.lookup_var(identifier.sym()) {
+            Some(local) => *local,
+            None => {
+                let ty = unsafe { LLVMTypeOf(value) };
+                let local = Local {
+                    slot: self.context.build_entry_alloca(ty, name),
+                    ty,
+                    kind: BindingKind::Var,
+                };
+
+                if !self.scopes.declare_var(identifier.sym(), local) {
+                    self.error(format!(
+                        "Cannot redeclare block-scoped variable `{}` with var",
+                        name
+                    ));
+                }
+
+                local
+            }
+        };
+
+        self.store_local(local, value);
+    }
+
+    fn compile_lexical_declarations(
+        &mut self,
+        declaration: &LexicalDeclaration,
+        interner: &Interner,
+    ) {
+        let kind = match declaration {
+            LexicalDeclaration::Let(_) => BindingKind::Let,
+            LexicalDeclaration::Const(_) => BindingKind::Const,
+        };
+        let variables: &[Variable] = declaration.variable_list().as_ref();
+
+        for variable in variables {
+            self.compile_lexical_declaration(variable, kind, interner);
+        }
+    }
+
+    fn compile_lexical_declaration(
+        &mut self,
+        variable: &Variable,
+        kind: BindingKind,
+        interner: &Interner,
+    ) {
+        let identifier = match variable.binding() {
+            Binding::Identifier(identifier) => *identifier,
+            Binding::Pattern(_) => {
+                self.unsupported("destructuring");
+                return;
+            }
+        };
+        let name = interner.resolve_expect(identifier.sym()).utf8().unwrap();
+
+        let value = match variable.init() {
+            Some(init) => self.compile_expression(init, interner).unwrap(),
+            None => self.context.create_undefined(),
+        };
+
+        let ty = unsafe { LLVMTypeOf(value) };
+        let slot = self.context.build_entry_alloca(ty, name);
+        let local = Local { slot, ty, kind };
+
+        if !self.scopes.declare_lexical(identifier.sym(), local) {
+            self.error(format!("Identifier `{}` has already been declared", name));
+        }
+
+        unsafe {
+            LLVMBuildStore(self.context.builder, value, slot);
+        }
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.push_diagnostic(Diagnostic::error(message));
+    }
+
+    // Codegen diagnostics point at the statement being compiled.
+    fn push_diagnostic(&mut self, mut diagnostic: Diagnostic) {
+        if let Some(position) = self.position {
+            diagnostic = diagnostic.with_position(position);
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    // Handles a construct that can't be lowered according to the unsupported
+    // policy, returning a placeholder for its value.
+    fn unsupported(&mut self, feature: &str) -> LLVMValueRef {
+        let message = format!("Unsupported feature: {}", feature);
+
+        match self.unsupported {
+            UnsupportedPolicy::Error => {
+                self.push_diagnostic(
+                    Diagnostic::error(message).with_note(
+                        "use --unsupported=warn to compile it as a runtime trap instead",
+                    ),
+                );
+
+                return self.context.create_undefined();
+            }
+            UnsupportedPolicy::Warn => self.push_diagnostic(Diagnostic::warning(message)),
+            UnsupportedPolicy::Trap => {}
+        }
+
+        let location = match self.position {
+            Some(position) => format!("at {}:{}", self.source_name, position.line_number()),
+            None => format!("in {}", self.source_name),
+        };
+        self.build_trap(&format!(
+            "reached unsupported feature {} {}\n",
+            feature, location
+        ));
+
+        self.context.create_undefined()
+    }
+
+    // Writes the message to stderr and aborts. Code after the trap goes into
+    // a block of its own, which is never reached.
+    fn build_trap(&self, message: &str) {
+        unsafe {
+            let context = self.context.context;
+            let int_type = LLVMInt32TypeInContext(context);
+            let size_type = LLVMInt64TypeInContext(context);
+            let mut param_types = vec![int_type, LLVMPointerTypeInContext(context, 0), size_type];
+            let write_type = LLVMFunctionType(
+                size_type,
+                param_types.as_mut_ptr(),
+                param_types.len() as u32,
+                0,
+            );
+            let write = self.context.get_or_declare_function("write", write_type);
+            let mut args = vec![
+                LLVMConstInt(int_type, 2, 0),
+                self.context.create_bytes_literal(message.as_bytes()),
+                LLVMConstInt(size_type, message.len() as u64, 0),
+            ];
+            LLVMBuildCall2(
+                self.context.builder,
+                write_type,
+                write,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                c"".as_ptr(),
+            );
+
+            let trap_type =
+                LLVMFunctionType(LLVMVoidTypeInContext(context), std::ptr::null_mut(), 0, 0);
+            let trap = self.context.get_or_declare_function("llvm.trap", trap_type);
+            LLVMBuildCall2(
+                self.context.builder,
+                trap_type,
+                trap,
+                std::ptr::null_mut(),
+                0,
+                c"".as_ptr(),
+            );
+            LLVMBuildUnreachable(self.context.builder);
+        }
+
+        self.start_unreachable_block();
+    }
+
+    // Code created at runtime through `eval` or the `Function` constructor
+    // can't be compiled ahead of time, so referring to either global is
+    // reported rather than linked as an unknown extern. Returns true if the
+    // error was reported.
+    fn reject_dynamic_code(
+        &mut self,
+        callee: &Expression,
+        global: &str,
+        interner: &Interner,
+    ) -> bool {
+        let Expression::Identifier(identifier) = callee else {
+            return false;
+        };
+
+        if interner.resolve_expect(identifier.sym()).utf8() != Some(global)
+            || self.scopes.lookup(identifier.sym()).is_some()
+            || self.scopes.lookup_captured(identifier.sym()).is_some()
+            || self.scopes.lookup_function(identifier.sym()).is_some()
+        {
+            return false;
+        }
+
+        self.push_diagnostic(
+            Diagnostic::error(format!("`{}` is not supported", global)).with_note(
+                "jscc compiles ahead of time and cannot run code created while the program runs",
+            ),
+        );
+
+        true
+    }
+
+    // Parameters are numbers until there is a boxed value representation, so
+    // other arguments can't be passed yet. Missing arguments are undefined and
+    // extra ones are evaluated but not passed.
+    fn compile_user_call(&mut self, call: &Call, interner: &Interner) -> Option<LLVMValueRef> {
+        let (function, depth) = match call.function() {
+            Expression::Identifier(identifier) => {
+                let sym = identifier.sym();
+
+                match self.scopes.lookup_function(sym) {
+                    Some(function) => function,
+                    // Locals only hold numbers, strings and booleans so far.
+                    None if self.scopes.lookup(sym).is_some()
+                        || self.scopes.lookup_captured(sym).is_some() =>
+                    {
+                        return Some(self.unsupported("calling a variable"));
+                    }
+                    None => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        let param_count = unsafe { LLVMCountParams(function) } as usize;
+        // Only functions nested in another function take an environment.
+        let mut args = vec![];
+        if depth > 0 {
+            args.push(self.build_environment(depth));
+        }
+        for arg in call.args() {
+            let value = self.compile_expression(arg, interner).unwrap();
+
+            if args.len() < param_count {
+                let value = if is_number_type(unsafe { LLVMTypeOf(value) }) {
+                    self.context.build_to_number(value)
+                } else {
+                    self.unsupported("passing a non-number argument to a function")
+                };
+
+                args.push(value);
+            }
+        }
+        args.resize(param_count, self.context.create_undefined());
+
+        Some(unsafe {
+            LLVMBuildCall2(
+                self.context.builder,
+                LLVMGlobalGetValueType(function),
+                function,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                c"".as_ptr(),
+            )
+        })
+    }
+
+    // Calls on the `__jscc` namespace are resolved at compile time rather
+    // than through a runtime object.
+    fn compile_builtin_call(&mut self, call: &Call, interner: &Interner) -> Option<LLVMValueRef> {
+        let access = match call.function() {
+            Expression::PropertyAccess(PropertyAccess::Simple(access)) => access,
+            _ => return None,
+        };
+
+        match access.target() {
+            Expression::Identifier(ident)
+                if interner.resolve_expect(ident.sym()).utf8() == Some("__jscc") => {}
+            _ => return None,
+        }
+
+        // Mistakes here are the user's, so they are reported at the call with a
+        // placeholder value rather than stopping codegen.
+        let position = self
+            .source_map
+            .locate(&call.to_interned_string(interner))
+            .or(self.position);
+        let report = |this: &mut Self, diagnostic: Diagnostic| {
+            this.diagnostics.push(match position {
+                Some(position) => diagnostic.with_position(position),
+                None => diagnostic,
+            });
+
+            Some(this.context.create_undefined())
+        };
+
+        let method = match access.field() {
+            PropertyAccessField::Const(sym) => interner.resolve_expect(*sym).utf8().unwrap(),
+            PropertyAccessField::Expr(_) => {
+                return report(
+                    self,
+                    Diagnostic::error("Computed access on `__jscc` is not supported"),
+                )
+            }
+        };
+
+        match method {
+            "embedFile" => {
+                let path = match call.args() {
+                    [Expression::Literal(Literal::String(path))] => {
+                        interner.resolve_expect(*path).utf8().unwrap()
+                    }
+                    _ => {
+                        return report(
+                            self,
+                            Diagnostic::error(
+                                "`__jscc.embedFile` expects a single string literal path",
+                            ),
+                        )
+                    }
+                };
+                let path = self.base_dir.join(path);
+
+                match std::fs::read(&path) {
+                    Ok(contents) => Some(self.context.create_bytes_literal(&contents)),
+                    Err(err) => report(
+                        self,
+                        Diagnostic::error(format!("Failed to embed {}: {}", path.display(), err)),
+                    ),
+                }
+            }
+            method => report(
+                self,
+                Diagnostic::error(format!("Unknown builtin `__jscc.{}`", method))
+                    .with_note("the only builtin is `__jscc.embedFile`"),
+            ),
+        }
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    pub fn compile_statement(
+        &mut self,
+        statement: &Statement,
+        interner: &Interner,
+    ) -> Option<LLVMValueRef> {
+        self.position = self
+            .source_map
+            .locate_next(&statement_head(statement, interner));
+
         match statement {
             boa_ast::Statement::Block(block) => {
+                self.scopes.push_block();
+                self.declare_functions(block.statement_list().iter(), interner);
+
                 for statement_list_item in block.statement_list().iter() {
-                    match statement_list_item {
-                        boa_ast::StatementListItem::Statement(_) => {
-                            self.compile_statement(statement, interner);
+                    self.compile_statement_list_item(statement_list_item, interner);
+                }
+                self.compile_function_bodies(block.statement_list().iter(), interner);
+
+                self.scopes.pop();
+
+                None
+            }
+            boa_ast::Statement::Var(declaration) => {
+                let variables: &[Variable] = declaration.0.as_ref();
+
+                for variable in variables {
+                    self.compile_var_declaration(variable, interner);
+                }
+
+                None
+            }
+            boa_ast::Statement::Empty => None,
+            boa_ast::Statement::Expression(expression) => {
+                self.compile_expression(expression, interner)
+            }
+            boa_ast::Statement::If(if_statement) => {
+                let condition = self
+                    .compile_expression(if_statement.cond(), interner)
+                    .unwrap();
+                let condition = self.context.build_to_boolean(condition);
+
+                let then_block = self.append_block(c"if.then");
+                let else_block = if_statement
+                    .else_node()
+                    .map(|_| self.append_block(c"if.else"));
+                let merge_block = self.append_block(c"if.end");
+
+                unsafe {
+                    LLVMBuildCondBr(
+                        self.context.builder,
+                        condition,
+                        then_block,
+                        else_block.unwrap_or(merge_block),
+                    );
+                    LLVMPositionBuilderAtEnd(self.context.builder, then_block);
+                }
+                self.compile_statement(if_statement.body(), interner);
+                self.branch_to(merge_block);
+
+                if let (Some(else_node), Some(else_block)) = (if_statement.else_node(), else_block)
+                {
+                    unsafe {
+                        LLVMPositionBuilderAtEnd(self.context.builder, else_block);
+                    }
+                    self.compile_statement(else_node, interner);
+                    self.branch_to(merge_block);
+                }
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, merge_block);
+                }
+
+                None
+            }
+            boa_ast::Statement::DoWhileLoop(do_while_loop) => {
+                let labels = std::mem::take(&mut self.pending_labels);
+                let body_block = self.append_block(c"do.body");
+                let condition_block = self.append_block(c"do.cond");
+                let end_block = self.append_block(c"do.end");
+
+                self.branch_to(body_block);
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, body_block);
+                }
+                self.compile_loop_body(
+                    do_while_loop.body(),
+                    labels,
+                    end_block,
+                    condition_block,
+                    interner,
+                );
+                self.branch_to(condition_block);
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, condition_block);
+                }
+                self.build_loop_condition(do_while_loop.cond(), body_block, end_block, interner);
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, end_block);
+                }
+
+                None
+            }
+            boa_ast::Statement::WhileLoop(while_loop) => {
+                let labels = std::mem::take(&mut self.pending_labels);
+                let condition_block = self.append_block(c"while.cond");
+                let body_block = self.append_block(c"while.body");
+                let end_block = self.append_block(c"while.end");
+
+                self.branch_to(condition_block);
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, condition_block);
+                }
+                self.build_loop_condition(while_loop.condition(), body_block, end_block, interner);
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, body_block);
+                }
+                self.compile_loop_body(
+                    while_loop.body(),
+                    labels,
+                    end_block,
+                    condition_block,
+                    interner,
+                );
+                self.branch_to(condition_block);
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, end_block);
+                }
+
+                None
+            }
+            boa_ast::Statement::ForLoop(for_loop) => {
+                let labels = std::mem::take(&mut self.pending_labels);
+                // A `let` or `const` in the initializer is scoped to the loop.
+                self.scopes.push_block();
+
+                match for_loop.init() {
+                    Some(ForLoopInitializer::Expression(expression)) => {
+                        self.compile_expression(expression, interner);
+                    }
+                    Some(ForLoopInitializer::Var(declaration)) => {
+                        let variables: &[Variable] = declaration.0.as_ref();
+
+                        for variable in variables {
+                            self.compile_var_declaration(variable, interner);
                         }
-                        boa_ast::StatementListItem::Declaration(_) => todo!(),
                     }
+                    Some(ForLoopInitializer::Lexical(declaration)) => {
+                        self.compile_lexical_declarations(declaration, interner);
+                    }
+                    None => {}
+                }
+
+                let condition_block = self.append_block(c"for.cond");
+                let body_block = self.append_block(c"for.body");
+                let update_block = self.append_block(c"for.update");
+                let end_block = self.append_block(c"for.end");
+
+                self.branch_to(condition_block);
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, condition_block);
+                }
+                match for_loop.condition() {
+                    Some(condition) => {
+                        self.build_loop_condition(condition, body_block, end_block, interner)
+                    }
+                    None => self.branch_to(body_block),
+                }
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, body_block);
+                }
+                self.compile_loop_body(for_loop.body(), labels, end_block, update_block, interner);
+                self.branch_to(update_block);
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, update_block);
+                }
+                if let Some(final_expr) = for_loop.final_expr() {
+                    self.compile_expression(final_expr, interner);
                 }
+                self.branch_to(condition_block);
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, end_block);
+                }
+                self.scopes.pop();
 
                 None
             }
-            boa_ast::Statement::Var(_) => todo!(),
-            boa_ast::Statement::Empty => todo!(),
-            boa_ast::Statement::Expression(expression) => {
-                self.compile_expression(expression, interner)
+            // Labels of an uncompiled loop would otherwise be claimed by the
+            // next loop.
+            boa_ast::Statement::ForInLoop(_) => {
+                self.pending_labels.clear();
+                self.unsupported("for-in loops");
+
+                None
+            }
+            boa_ast::Statement::ForOfLoop(_) => {
+                self.pending_labels.clear();
+                self.unsupported("for-of loops");
+
+                None
+            }
+            boa_ast::Statement::Switch(switch) => {
+                let value = self.compile_expression(switch.val(), interner).unwrap();
+                let cases = switch.cases();
+
+                // Each clause gets a block, and a clause without a `break`
+                // falls through into the next one.
+                let case_blocks = cases
+                    .iter()
+                    .map(|case| match case.condition() {
+                        Some(_) => self.append_block(c"switch.case"),
+                        None => self.append_block(c"switch.default"),
+                    })
+                    .collect::<Vec<_>>();
+                let end_block = self.append_block(c"switch.end");
+                let default_block = cases
+                    .iter()
+                    .position(|case| case.condition().is_none())
+                    .map_or(end_block, |index| case_blocks[index]);
+
+                let constant_cases = cases
+                    .iter()
+                    .zip(&case_blocks)
+                    .filter_map(|(case, block)| Some((case.condition()?, *block)))
+                    .map(|(condition, block)| Some((constant_case_value(condition)?, block)))
+                    .collect::<Option<Vec<_>>>();
+                let is_number = unsafe {
+                    LLVMGetTypeKind(LLVMTypeOf(value)) == LLVMTypeKind::LLVMDoubleTypeKind
+                };
+
+                match constant_cases {
+                    Some(constant_cases) if is_number => {
+                        self.build_switch_dispatch(value, &constant_cases, default_block)
+                    }
+                    _ => {
+                        for (case, block) in cases.iter().zip(&case_blocks) {
+                            let Some(condition) = case.condition() else {
+                                continue;
+                            };
+                            if self.is_undefined(condition, interner) {
+                                self.unsupported("comparing against `undefined`");
+                                continue;
+                            }
+                            let condition = self.compile_expression(condition, interner).unwrap();
+                            let matches = self.context.build_strict_equals(value, condition);
+                            let next_block = self.append_block(c"switch.test");
+
+                            unsafe {
+                                LLVMBuildCondBr(self.context.builder, matches, *block, next_block);
+                                LLVMPositionBuilderAtEnd(self.context.builder, next_block);
+                            }
+                        }
+
+                        self.branch_to(default_block);
+                    }
+                }
+
+                self.scopes.push_block();
+                self.declare_functions(
+                    cases.iter().flat_map(|case| case.body().statements()),
+                    interner,
+                );
+                self.jump_targets.push(JumpTarget {
+                    labels: vec![],
+                    kind: JumpKind::Switch,
+                    break_block: end_block,
+                });
+
+                for (index, case) in cases.iter().enumerate() {
+                    unsafe {
+                        LLVMPositionBuilderAtEnd(self.context.builder, case_blocks[index]);
+                    }
+
+                    for item in case.body().statements() {
+                        self.compile_statement_list_item(item, interner);
+                    }
+
+                    self.branch_to(case_blocks.get(index + 1).copied().unwrap_or(end_block));
+                }
+                self.compile_function_bodies(
+                    cases.iter().flat_map(|case| case.body().statements()),
+                    interner,
+                );
+
+                self.jump_targets.pop();
+                self.scopes.pop();
+
+                unsafe {
+                    LLVMPositionBuilderAtEnd(self.context.builder, end_block);
+                }
+
+                None
+            }
+            boa_ast::Statement::Continue(continue_statement) => {
+                let label = continue_statement.label();
+                let target = self
+                    .jump_targets
+                    .iter()
+                    .rev()
+                    .find_map(|target| match target.kind {
+                        JumpKind::Loop { continue_block } => match label {
+                            Some(label) if !target.labels.contains(&label) => None,
+                            _ => Some(continue_block),
+                        },
+                        JumpKind::Switch | JumpKind::Labelled => None,
+                    });
+
+                self.build_jump(target, label, "continue", interner);
+
+                None
+            }
+            boa_ast::Statement::Break(break_statement) => {
+                // Without a label, `break` leaves the innermost loop or switch
+                // but never a labelled block.
+                let label = break_statement.label();
+                let target = self
+                    .jump_targets
+                    .iter()
+                    .rev()
+                    .find(|target| match label {
+                        Some(label) => target.labels.contains(&label),
+                        None => !matches!(target.kind, JumpKind::Labelled),
+                    })
+                    .map(|target| target.break_block);
+
+                self.build_jump(target, label, "break", interner);
+
+                None
+            }
+            boa_ast::Statement::Return(ret) => {
+                // Functions return numbers until there is a boxed value
+                // representation.
+                let value = match ret.target() {
+                    Some(target) => {
+                        let value = self.compile_expression(target, interner).unwrap();
+
+                        if is_number_type(unsafe { LLVMTypeOf(value) }) {
+                            self.context.build_to_number(value)
+                        } else {
+                            self.unsupported("returning a non-number value from a function")
+                        }
+                    }
+                    None => self.context.create_undefined(),
+                };
+
+                unsafe {
+                    LLVMBuildRet(self.context.builder, value);
+                }
+                self.start_unreachable_block();
+
+                None
+            }
+            boa_ast::Statement::Labelled(labelled) => {
+                match labelled.item() {
+                    LabelledItem::Statement(statement) if is_iteration(statement) => {
+                        // The loop picks the label up so `continue label`
+                        // can find it.
+                        self.pending_labels.push(labelled.label());
+                        self.compile_statement(statement, interner);
+                    }
+                    LabelledItem::Statement(statement) => {
+                        let end_block = self.append_block(c"label.end");
+
+                        self.jump_targets.push(JumpTarget {
+                            labels: vec![labelled.label()],
+                            kind: JumpKind::Labelled,
+                            break_block: end_block,
+                        });
+                        self.compile_statement(statement, interner);
+                        self.jump_targets.pop();
+
+                        self.branch_to(end_block);
+                        unsafe {
+                            LLVMPositionBuilderAtEnd(self.context.builder, end_block);
+                        }
+                    }
+                    LabelledItem::FunctionDeclaration(function) => {
+                        self.compile_function_declaration(function, interner);
+                    }
+                }
+
+                None
+            }
+            boa_ast::Statement::Throw(_) => {
+                self.unsupported("`throw`");
+
+                None
+            }
+            boa_ast::Statement::Try(_) => {
+                self.unsupported("`try`");
+
+                None
+            }
+            boa_ast::Statement::With(_) => {
+                self.unsupported("`with`");
+
+                None
+            }
+        }
+    }
+}
+
+// `export function f() {}`, the only kind of export that compiles so far.
+fn exported_function(item: &ModuleItem) -> Option<&FunctionDeclaration> {
+    match item {
+        ModuleItem::ExportDeclaration(ExportDeclaration::Declaration(
+            Declaration::FunctionDeclaration(function),
+        )) => Some(function),
+        _ => None,
+    }
+}
+
+// Numbers are doubles, apart from the i32 results of calls to C functions.
+// i1 is a boolean.
+fn is_number_type(ty: LLVMTypeRef) -> bool {
+    unsafe {
+        match LLVMGetTypeKind(ty) {
+            LLVMTypeKind::LLVMDoubleTypeKind => true,
+            LLVMTypeKind::LLVMIntegerTypeKind => LLVMGetIntTypeWidth(ty) != 1,
+            _ => false,
+        }
+    }
+}
+
+// Integer literals that a switch case can match through an LLVM switch.
+fn constant_case_value(expression: &Expression) -> Option<i64> {
+    match expression {
+        Expression::Literal(Literal::Int(value)) => Some(i64::from(*value)),
+        Expression::Literal(Literal::Num(value))
+            if value.fract() == 0.0 && value.abs() < i64::MAX as f64 =>
+        {
+            Some(*value as i64)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn is_iteration(statement: &Statement) -> bool {
+    match statement {
+        Statement::DoWhileLoop(_)
+        | Statement::WhileLoop(_)
+        | Statement::ForLoop(_)
+        | Statement::ForInLoop(_)
+        | Statement::ForOfLoop(_) => true,
+        Statement::Labelled(labelled) => {
+            matches!(labelled.item(), LabelledItem::Statement(statement) if is_iteration(statement))
+        }
+        _ => false,
+    }
+}
+
+unsafe fn block_name(block: LLVMBasicBlockRef) -> String {
+    CStr::from_ptr(LLVMGetBasicBlockName(block))
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boa_parser::{Parser, Source};
+    use llvm_sys::analysis::{LLVMVerifierFailureAction, LLVMVerifyModule};
+    use llvm_sys::execution_engine::*;
+
+    // Compiles a script the way the CLI does, without finishing the root
+    // function.
+    fn generate(codegen: &mut CodeGenerator, source: &str) {
+        let mut interner = Interner::new();
+        let script = Parser::new(Source::from_bytes(source))
+            .parse_script(&mut interner)
+            .unwrap();
+        let statements = script.statements();
+
+        codegen.source_map = SourceMap::new(source);
+        codegen.declare_vars(statements.iter(), &interner);
+        codegen.declare_functions(statements.iter(), &interner);
+        for item in statements.iter() {
+            codegen.compile_script_item(item, &interner);
+        }
+        codegen.compile_function_bodies(statements.iter(), &interner);
+    }
+
+    // Compiles a module and its annotations the way the CLI does, without
+    // finishing the root function.
+    fn generate_module(codegen: &mut CodeGenerator, source: &str) {
+        let mut interner = Interner::new();
+        let module = Parser::new(Source::from_bytes(source))
+            .parse_module(&mut interner)
+            .unwrap();
+        let items = module.items().items();
+        let statements = items.iter().filter_map(|item| match item {
+            ModuleItem::StatementListItem(item) => Some(item),
+            _ => None,
+        });
+
+        codegen.source_map = SourceMap::new(source);
+        codegen.annotations = pragma::scan_annotations(source).unwrap();
+        codegen.declare_vars(statements.clone(), &interner);
+        codegen.declare_exported_functions(items, &interner);
+        codegen.declare_functions(statements.clone(), &interner);
+        for item in items {
+            codegen.compile_module_item(item, &interner);
+        }
+        codegen.compile_function_bodies(statements, &interner);
+        codegen.compile_exported_function_bodies(items, &interner);
+    }
+
+    fn compile(source: &str) -> CodeGenerator {
+        compile_with(CodeGenerator::default(), source)
+    }
+
+    // Compiles a script that must compile without diagnostics into a module
+    // that passes the verifier.
+    fn compile_with(mut codegen: CodeGenerator, source: &str) -> CodeGenerator {
+        codegen.verify_each = true;
+        generate(&mut codegen, source);
+
+        let diagnostics = messages(&codegen);
+        assert!(
+            diagnostics.is_empty(),
+            "unexpected diagnostics: {:?}",
+            diagnostics
+        );
+        codegen.finish_root_function().unwrap();
+
+        unsafe {
+            let mut message = std::ptr::null_mut();
+            let failed = LLVMVerifyModule(
+                codegen.context.module,
+                LLVMVerifierFailureAction::LLVMReturnStatusAction,
+                &mut message,
+            );
+            let error = CStr::from_ptr(message).to_string_lossy().into_owned();
+            LLVMDisposeMessage(message);
+
+            assert_eq!(failed, 0, "{}\n{}", error, ir(&codegen));
+        }
+
+        codegen
+    }
+
+    fn messages(codegen: &CodeGenerator) -> Vec<String> {
+        codegen
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect()
+    }
+
+    // The messages of the diagnostics a script compiles with.
+    fn errors(source: &str) -> Vec<String> {
+        let mut codegen = CodeGenerator::default();
+        generate(&mut codegen, source);
+
+        messages(&codegen)
+    }
+
+    fn ir(codegen: &CodeGenerator) -> String {
+        unsafe {
+            let ir = LLVMPrintModuleToString(codegen.context.module);
+            let string = CStr::from_ptr(ir).to_string_lossy().into_owned();
+            LLVMDisposeMessage(ir);
+
+            string
+        }
+    }
+
+    // Runs the top-level function `name`, which must take no arguments, and
+    // returns its result.
+    fn call(codegen: &CodeGenerator, name: &str) -> f64 {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| unsafe {
+            LLVMLinkInMCJIT();
+            assert_eq!(LLVM_InitializeNativeTarget(), 0);
+            assert_eq!(LLVM_InitializeNativeAsmPrinter(), 0);
+        });
+
+        unsafe {
+            // Functions with internal linkage can't be looked up once the
+            // module is compiled.
+            let symbol = CString::new(format!("js.{}", name)).unwrap();
+            let function = LLVMGetNamedFunction(codegen.context.module, symbol.as_ptr());
+            assert!(!function.is_null(), "`{}` is not defined", name);
+            LLVMSetLinkage(function, LLVMLinkage::LLVMExternalLinkage);
+
+            let mut engine = std::ptr::null_mut();
+            let mut error = std::ptr::null_mut();
+            if LLVMCreateExecutionEngineForModule(&mut engine, codegen.context.module, &mut error)
+                != 0
+            {
+                panic!("{}", CStr::from_ptr(error).to_string_lossy());
             }
-            boa_ast::Statement::If(_) => todo!(),
-            boa_ast::Statement::DoWhileLoop(_) => todo!(),
-            boa_ast::Statement::WhileLoop(_) => todo!(),
-            boa_ast::Statement::ForLoop(_) => todo!(),
-            boa_ast::Statement::ForInLoop(_) => todo!(),
-            boa_ast::Statement::ForOfLoop(_) => todo!(),
-            boa_ast::Statement::Switch(_) => todo!(),
-            boa_ast::Statement::Continue(_) => todo!(),
-            boa_ast::Statement::Break(_) => todo!(),
-            boa_ast::Statement::Return(_) => todo!(),
-            boa_ast::Statement::Labelled(_) => todo!(),
-            boa_ast::Statement::Throw(_) => todo!(),
-            boa_ast::Statement::Try(_) => todo!(),
-            boa_ast::Statement::With(_) => todo!(),
+
+            let address = LLVMGetFunctionAddress(engine, symbol.as_ptr());
+            assert_ne!(address, 0);
+            let function = std::mem::transmute::<usize, extern "C" fn() -> f64>(address as usize);
+            let result = function();
+
+            // The context disposes of the module, so the engine must not.
+            let mut module = std::ptr::null_mut();
+            LLVMRemoveModule(engine, codegen.context.module, &mut module, &mut error);
+            LLVMDisposeExecutionEngine(engine);
+
+            result
         }
     }
+
+    fn run(source: &str) -> f64 {
+        call(&compile(source), "f")
+    }
+
+    #[test]
+    fn compiles_arithmetic() {
+        assert_eq!(run("function f() { return (7 + 3) * 2 - 10 / 4; }"), 17.5);
+        assert_eq!(run("function f() { return 5 % -3 + -5 % 3 * 10; }"), -18.0);
+        assert_eq!(run("function f() { return ((1 + 2)) * 3; }"), 9.0);
+    }
+
+    #[test]
+    fn compiles_comparisons() {
+        let source = "function f() {
+            return (1 < 2) + (2 <= 2) + (3 > 4) + (4 >= 5) + ('a' < 'b') + (NaN == NaN)
+                + (NaN != NaN);
+        }";
+
+        assert_eq!(run(source), 4.0);
+    }
+
+    #[test]
+    fn compiles_unary_operators() {
+        assert_eq!(run("function f() { return -(3) + !0 + ~5; }"), -8.0);
+        assert!(run("function f() { return void 1; }").is_nan());
+    }
+
+    #[test]
+    fn wraps_bitwise_operands_modulo_2_32() {
+        assert_eq!(run("function f() { return 4294967297 | 0; }"), 1.0);
+        assert_eq!(
+            run("function f() { return -2147483649 | 0; }"),
+            2147483647.0
+        );
+        assert_eq!(run("function f() { return 1e20 | 0; }"), 1661992960.0);
+        assert_eq!(run("function f() { return -1e20 | 0; }"), -1661992960.0);
+        assert_eq!(run("function f() { return ~1e20; }"), -1661992961.0);
+        assert_eq!(
+            run("function f() { return 4294967295.5 >>> 0; }"),
+            4294967295.0
+        );
+        assert_eq!(run("function f() { return NaN | 0; }"), 0.0);
+        assert_eq!(run("function f() { return Infinity | 0; }"), 0.0);
+        assert_eq!(run("function f() { return -Infinity | 0; }"), 0.0);
+    }
+
+    #[test]
+    fn short_circuits_logical_operators() {
+        let source = "function f() {
+            var x = 0;
+            var r = 0 && (x = 1);
+            var s = 2 || (x = 2);
+            var t = 3 && 4;
+            return x * 1000 + r * 100 + s * 10 + t;
+        }";
+
+        assert_eq!(run(source), 24.0);
+    }
+
+    #[test]
+    fn compiles_conditional_expressions() {
+        let source = "function f() {
+            var a = 1 ? 2 : 3;
+            var b = 0 ? 2 : 3;
+            return a * 10 + b;
+        }";
+
+        assert_eq!(run(source), 23.0);
+    }
+
+    #[test]
+    fn reports_merging_values_of_different_types() {
+        assert_eq!(
+            errors("var x = 1 && 'a';"),
+            ["Unsupported feature: `&&` and `||` on operands of different types"]
+        );
+        assert_eq!(
+            errors("var x = true ? 1 : 'a';"),
+            ["Unsupported feature: conditional expressions with arms of different types"]
+        );
+    }
+
+    #[test]
+    fn types_var_slots_by_their_initializers() {
+        assert_eq!(
+            run("function f() { var s = 'hi'; return s === 'hi' ? 1 : 0; }"),
+            1.0
+        );
+        assert_eq!(
+            run("function f() { var ok = true; return ok ? 1 : 0; }"),
+            1.0
+        );
+        assert!(errors("var s = 'hi';\nvar ok = true;\nvar n = strlen(s);").is_empty());
+        assert_eq!(
+            errors("var x = 1;\nvar x = 'a';"),
+            ["Unsupported feature: changing the type of a variable"]
+        );
+    }
+
+    #[test]
+    fn keeps_the_slot_of_redeclared_vars() {
+        assert_eq!(
+            run("function f() { var x = 1; var x = 2; var x; return x; }"),
+            2.0
+        );
+
+        let source = "function f() { var s = 'a'; var s = 'b'; return s === 'b' ? 1 : 0; }";
+        assert_eq!(run(source), 1.0);
+    }
+
+    #[test]
+    fn reads_vars_before_their_assignment() {
+        assert!(run("function f() { var before = x; var x = 1; return before; }").is_nan());
+
+        let source = "function f() { var before = s ? 1 : 0; var s = 'a'; return before; }";
+        assert_eq!(run(source), 0.0);
+    }
+
+    #[test]
+    fn compiles_assignments() {
+        let source = "function f() {
+            var x;
+            var y = (x = 4) + 1;
+            return x * 10 + y;
+        }";
+
+        assert_eq!(run(source), 45.0);
+    }
+
+    #[test]
+    fn compiles_compound_assignments() {
+        let source = "function f() {
+            var x = 6;
+            x += 2; x *= 3; x -= 4; x /= 5; x %= 3;
+            x <<= 3; x >>= 1; x |= 1; x ^= 3; x &= 6;
+            return x;
+        }";
+
+        assert_eq!(run(source), 6.0);
+    }
+
+    #[test]
+    fn skips_the_store_of_short_circuited_logical_assignments() {
+        let source = "function f() {
+            var x = 0;
+            var y = 1;
+            var stored = 0;
+            x &&= (stored = 1);
+            y ||= (stored += 10);
+            var a = 2;
+            var b = 0;
+            var c = (a &&= 3) + (b ||= 4);
+            return stored * 1000 + a * 100 + b * 10 + c;
+        }";
+
+        assert_eq!(run(source), 347.0);
+        assert_eq!(
+            errors("var x = 1;\nx ??= 2;"),
+            ["Unsupported feature: nullish coalescing"]
+        );
+    }
+
+    #[test]
+    fn returns_the_right_value_from_updates() {
+        let source = "function f() {
+            var i = 5;
+            var a = i++;
+            var b = ++i;
+            var c = i--;
+            var d = --i;
+            return a * 1000 + b * 100 + c * 10 + d;
+        }";
+
+        assert_eq!(run(source), 5775.0);
+    }
+
+    #[test]
+    fn scopes_lexical_declarations_to_blocks() {
+        let source = "function f() {
+            let x = 1;
+            { let x = 2; x = 3; }
+            return x;
+        }";
+
+        assert_eq!(run(source), 1.0);
+    }
+
+    #[test]
+    fn rejects_assigning_to_constants() {
+        assert_eq!(
+            errors("const a = 1;\na = 2;\na++;"),
+            [
+                "Cannot assign to `a` because it is a constant",
+                "Cannot assign to `a` because it is a constant",
+            ]
+        );
+    }
+
+    #[test]
+    fn compiles_if_statements() {
+        let source = "function f() {
+            var x = 0;
+            if (x) { x = 1; } else if ('') { x = 2; } else { x = 3; }
+            return x;
+        }";
+
+        assert_eq!(run(source), 3.0);
+    }
+
+    #[test]
+    fn compiles_loops() {
+        let source = "function f() {
+            var total = 0;
+            for (let i = 0; i < 5; i++) { total += i; }
+            return total;
+        }";
+        assert_eq!(run(source), 10.0);
+
+        let source = "function f() { var n = 0; while (n < 3) n++; return n; }";
+        assert_eq!(run(source), 3.0);
+
+        // The body runs once before the condition is first checked.
+        let source = "function f() { var n = 0; do { n++; } while (false); return n; }";
+        assert_eq!(run(source), 1.0);
+    }
+
+    #[test]
+    fn jumps_to_labelled_targets() {
+        let source = "function f() {
+            var total = 0;
+            outer: for (var i = 0; i < 3; i++) {
+                for (var j = 0; j < 3; j++) {
+                    if (j == 1) continue outer;
+                    if (i == 2) break outer;
+                    total += 10 * i + j;
+                }
+            }
+            return total;
+        }";
+        assert_eq!(run(source), 10.0);
+
+        let source = "function f() {
+            var out = 0;
+            block: { out = 1; break block; out = 2; }
+            return out;
+        }";
+        assert_eq!(run(source), 1.0);
+    }
+
+    #[test]
+    fn falls_through_switch_cases() {
+        let source = "function f() {
+            var out = 0;
+            switch (2) {
+                case 1: out += 1;
+                case 2: out += 10;
+                case 3: out += 100; break;
+                case 4: out += 1000;
+                default: out += 10000;
+            }
+            return out;
+        }";
+        assert_eq!(run(source), 110.0);
+
+        let source = "function f() {
+            var out = 0;
+            switch (7) { case 1: out = 1; break; default: out = 5; case 2: out += 1; }
+            return out;
+        }";
+        assert_eq!(run(source), 6.0);
+
+        // Cases that aren't integer constants are tested one by one.
+        let source = "function f() {
+            var out = 0;
+            switch ('b') { case 'a': out = 1; break; case 'b': out = 2; case 'c': out += 3; }
+            return out;
+        }";
+        assert_eq!(run(source), 5.0);
+    }
+
+    #[test]
+    fn calls_user_functions() {
+        let source = "function f() { return add(2, 3); }
+            function add(a, b) { return a + b; }";
+        assert_eq!(run(source), 5.0);
+
+        // Missing arguments and bare returns are undefined.
+        let source = "function one(a) { return a; } function f() { return one(); }";
+        assert!(run(source).is_nan());
+        assert!(run("function f() { return; }").is_nan());
+        assert_eq!(run("function f() { return 1; return 2; }"), 1.0);
+
+        let source = "function f() {
+            var x = 1;
+            function g() { x = x + 1; }
+            g();
+            g();
+            return x;
+        }";
+        assert_eq!(run(source), 3.0);
+    }
+
+    #[test]
+    fn does_not_call_functions_shadowed_by_locals() {
+        assert_eq!(
+            errors("function f() {}\n{ let f = 1; f(); }"),
+            ["Unsupported feature: calling a variable"]
+        );
+        assert_eq!(
+            errors("function g() {}\nfunction f(g) { return g(); }"),
+            ["Unsupported feature: calling a variable"]
+        );
+
+        // A function declared closer than the local is called.
+        let source = "function f() {
+            let g = 1;
+            { function g() { return 2; } return g(); }
+        }";
+        assert_eq!(run(source), 2.0);
+    }
+
+    #[test]
+    fn reports_non_number_arguments_and_return_values() {
+        assert_eq!(
+            errors("function f(a) {}\nf('s');"),
+            ["Unsupported feature: passing a non-number argument to a function"]
+        );
+        assert_eq!(
+            errors("function f() { return 's'; }"),
+            ["Unsupported feature: returning a non-number value from a function"]
+        );
+    }
+
+    #[test]
+    fn scopes_block_functions_by_annex_b() {
+        let source = "function f() { { function g() { return 2; } } return g(); }";
+
+        let codegen = compile_with(
+            CodeGenerator {
+                annex_b: true,
+                ..CodeGenerator::default()
+            },
+            source,
+        );
+        assert_eq!(call(&codegen, "f"), 2.0);
+
+        // Without Annex B the block's function isn't visible, so `g` is an
+        // extern.
+        let codegen = compile(source);
+        assert!(unsafe { !LLVMGetNamedFunction(codegen.context.module, c"g".as_ptr()).is_null() });
+    }
+
+    #[test]
+    fn exports_functions_under_their_symbols() {
+        let source = r#"
+            /* @jscc export_name("js_add") */
+            export function add(a, b) { return a + b; }
+            export function tick() { return 1; }
+            /* @jscc hidden */
+            export function secret() { return 2; }
+        "#;
+        let mut codegen = CodeGenerator {
+            rust_bindings: true,
+            ..CodeGenerator::default()
+        };
+        generate_module(&mut codegen, source);
+
+        assert!(messages(&codegen).is_empty());
+        assert_eq!(
+            codegen.exports,
+            [("js_add".to_string(), 2), ("tick".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn applies_annotations_to_the_declaration_they_precede() {
+        let source = r#"
+            /* @jscc inline */
+            export function twice(x) { return x * 2; }
+            export function outer() {
+                function twice(x) { return x; }
+                return twice(1);
+            }
+        "#;
+        let mut codegen = CodeGenerator::default();
+        generate_module(&mut codegen, source);
+        assert!(messages(&codegen).is_empty());
+
+        let always_inline = |name: &CStr| unsafe {
+            let function = LLVMGetNamedFunction(codegen.context.module, name.as_ptr());
+            assert!(!function.is_null(), "{:?}", name);
+            let kind = LLVMGetEnumAttributeKindForName(c"alwaysinline".as_ptr(), 12);
+
+            !LLVMGetEnumAttributeAtIndex(function, LLVMAttributeFunctionIndex, kind).is_null()
+        };
+        assert!(always_inline(c"twice"));
+        assert!(!always_inline(c"js.twice"));
+        assert!(!always_inline(c"outer"));
+    }
+
+    #[test]
+    fn rejects_export_symbols_reserved_in_rust() {
+        let source = r#"
+            /* @jscc export_name("type") */
+            export function kind() { return 1; }
+            export function ffi() { return 2; }
+        "#;
+        let mut codegen = CodeGenerator {
+            rust_bindings: true,
+            ..CodeGenerator::default()
+        };
+        generate_module(&mut codegen, source);
+
+        assert_eq!(
+            messages(&codegen),
+            [
+                "The symbol `type` is reserved in the generated Rust bindings",
+                "The symbol `ffi` is reserved in the generated Rust bindings",
+            ]
+        );
+        assert!(codegen.exports.is_empty());
+
+        // Without bindings only C has to accept the symbols.
+        let mut codegen = CodeGenerator::default();
+        generate_module(&mut codegen, source);
+        assert!(messages(&codegen).is_empty());
+    }
+
+    #[test]
+    fn applies_the_unsupported_policy() {
+        assert_eq!(errors("throw 1;"), ["Unsupported feature: `throw`"]);
+
+        let codegen = compile_with(
+            CodeGenerator {
+                unsupported: UnsupportedPolicy::Trap,
+                source_name: "test.js".to_string(),
+                ..CodeGenerator::default()
+            },
+            "function f() {\n  throw 1;\n}",
+        );
+        assert!(ir(&codegen).contains("reached unsupported feature `throw` at test.js:2"));
+    }
 }
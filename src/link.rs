@@ -0,0 +1,277 @@
+use crate::diagnostics::Diagnostic;
+use crate::output::PendingOutput;
+use crate::target::Target;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, instrument};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputKind {
+    #[default]
+    Executable,
+    StaticLib,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LinkerFlavor {
+    // The target's default C compiler driver.
+    #[default]
+    Cc,
+    Clang,
+    Lld,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LinkOptions {
+    pub flavor: LinkerFlavor,
+    pub frameworks: Vec<String>,
+    pub rpaths: Vec<String>,
+    pub libraries: Vec<String>,
+    pub search_paths: Vec<PathBuf>,
+    pub args: Vec<String>,
+}
+
+// Links into the temporary file of `output`, returning warnings about the
+// linked executable.
+#[instrument(skip_all, fields(output = %output.path().display()))]
+pub fn link_executable(
+    object: &Path,
+    output: &PendingOutput,
+    target: &Target,
+    options: &LinkOptions,
+) -> Result<Vec<Diagnostic>, String> {
+    run_tool(
+        &mut link_command(object, output.temp_path(), target, options),
+        "linker",
+    )?;
+
+    let mut warnings = vec![];
+
+    // arm64 macOS refuses to execute unsigned binaries, so give the output an
+    // ad-hoc signature in case the linker didn't already. Cross toolchains
+    // rarely ship codesign, so without one the binary is left as linked.
+    if target.is_macos() && target.is_arm64() {
+        if cfg!(target_os = "macos") || is_on_path("codesign") {
+            run_tool(&mut codesign_command(output), "codesign")?;
+        } else {
+            warnings.push(
+                Diagnostic::warning("codesign was not found, so the executable is not signed")
+                    .with_note("arm64 macOS only runs signed executables"),
+            );
+        }
+    }
+
+    Ok(warnings)
+}
+
+// The signature covers the file's contents, which the rename that commits the
+// output keeps, so the temporary file is signed under the final name instead
+// of leaving an unsigned executable in place if codesign fails.
+fn codesign_command(output: &PendingOutput) -> Command {
+    let identifier = output.path().file_name().unwrap_or_default();
+    let mut command = Command::new("codesign");
+    command
+        .args(["--force", "--sign", "-", "--identifier"])
+        .arg(identifier)
+        .arg(output.temp_path());
+
+    command
+}
+
+fn link_command(object: &Path, output: &Path, target: &Target, options: &LinkOptions) -> Command {
+    let mut command = match options.flavor {
+        LinkerFlavor::Cc => target.linker(),
+        LinkerFlavor::Clang => {
+            let mut command = Command::new("clang");
+            command
+                .arg(format!("--target={}", target.clang_triple()))
+                .args(target.platform_args());
+            command
+        }
+        LinkerFlavor::Lld => {
+            let mut command = target.linker();
+            command.arg("-fuse-ld=lld");
+            command
+        }
+    };
+    command.arg(object).arg("-o").arg(output);
+
+    if target.static_link {
+        command.arg("-static");
+    }
+
+    // Android only runs position independent executables, unless they are
+    // static.
+    if target.is_android() && !target.static_link {
+        command.arg("-pie");
+    }
+
+    if target.is_macos() {
+        command.arg(format!(
+            "-Wl,-platform_version,macos,{},{}",
+            target.macos_version_min(),
+            target.macos_sdk_version()
+        ));
+    }
+
+    if target.is_apple() {
+        for framework in &options.frameworks {
+            command.args(["-framework", framework]);
+        }
+    }
+
+    for rpath in &options.rpaths {
+        command.arg(format!("-Wl,-rpath,{}", rpath));
+    }
+
+    // Libraries come after the object so the linker sees the references to
+    // them first.
+    for search_path in &options.search_paths {
+        command.arg("-L").arg(search_path);
+    }
+
+    for library in &options.libraries {
+        command.arg(format!("-l{}", library));
+    }
+
+    command.args(&options.args);
+
+    command
+}
+
+#[instrument(skip_all, fields(output = %output.display()))]
+pub fn create_static_library(object: &Path, output: &Path, target: &Target) -> Result<(), String> {
+    // Replace rather than update an existing archive so stale members from a
+    // previous build never end up in the output.
+    let _ = std::fs::remove_file(output);
+
+    let mut command = if target.is_apple() {
+        let mut command = Command::new("libtool");
+        command.arg("-static").arg("-o").arg(output).arg(object);
+        command
+    } else {
+        let mut command = Command::new("ar");
+        command.arg("rcs").arg(output).arg(object);
+        command
+    };
+
+    run_tool(&mut command, "archiver")
+}
+
+fn is_on_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path).any(|directory| directory.join(program).is_file())
+    })
+}
+
+// Runs an external build tool, forwarding its diagnostics on success and
+// folding them into the error on failure.
+fn run_tool(command: &mut Command, tool: &str) -> Result<(), String> {
+    debug!(?command, "running {}", tool);
+    let output = command.output().map_err(|err| {
+        format!(
+            "Failed to run {} {:?}: {}",
+            tool,
+            command.get_program(),
+            err
+        )
+    })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if !output.status.success() {
+        let mut message = format!("The {} failed ({}): {:?}", tool, output.status, command);
+
+        for stream in [stderr.trim_end(), stdout.trim_end()] {
+            if !stream.is_empty() {
+                message += "\n";
+                message += stream;
+            }
+        }
+
+        return Err(message);
+    }
+
+    if !stderr.trim().is_empty() {
+        eprint!("{}", stderr);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    fn link_args(target: &Target) -> Vec<String> {
+        let command = link_command(
+            Path::new("main.o"),
+            Path::new("main"),
+            target,
+            &LinkOptions::default(),
+        );
+
+        command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn links_for_the_macos_version_and_arch() {
+        let mut target = Target::new("aarch64-apple-darwin");
+        target.min_os_version = Some("12.0".to_string());
+        let args = link_args(&target);
+
+        assert_eq!(args[..5], ["-arch", "arm64", "main.o", "-o", "main"]);
+        assert!(args[5].starts_with("-Wl,-platform_version,macos,12.0,"));
+        assert_eq!(args.len(), 6);
+    }
+
+    #[test]
+    fn signs_under_the_final_name() {
+        let output = PendingOutput::new(Path::new("out/main"));
+        let command = codesign_command(&output);
+        let args: Vec<_> = command.get_args().collect();
+
+        assert_eq!(command.get_program(), "codesign");
+        assert_eq!(
+            args,
+            ["--force", "--sign", "-", "--identifier", "main"]
+                .map(OsStr::new)
+                .into_iter()
+                .chain([output.temp_path().as_os_str()])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn links_android_executables_as_pie_unless_static() {
+        let mut target = Target::new("aarch64-linux-android");
+        target.sysroot = None;
+        assert_eq!(
+            link_args(&target),
+            [
+                "--target=aarch64-linux-android21",
+                "main.o",
+                "-o",
+                "main",
+                "-pie"
+            ]
+        );
+
+        target.static_link = true;
+        assert_eq!(
+            link_args(&target),
+            [
+                "--target=aarch64-linux-android21",
+                "main.o",
+                "-o",
+                "main",
+                "-static"
+            ]
+        );
+    }
+}
@@ -1,35 +1,289 @@
-use boa_interner::Interner;
+use boa_ast::{ModuleItem, StatementListItem};
+use boa_interner::{Interner, ToInternedString};
 use boa_parser::{Parser, Source};
+use cli::{is_typescript, Command, Emit, InputKind, Options};
+use jscc::check::{has_use_strict_directive, EarlyErrors};
+use jscc::diagnostics::{Diagnostic, DiagnosticEmitter};
+use jscc::header::{c_identifier, write_header, write_rust_bindings};
+use jscc::link::{create_static_library, link_executable, OutputKind};
+use jscc::output::{is_same_file, PendingOutput};
+use jscc::pragma::{self, Pragma};
+use jscc::source_map::SourceMap;
+use jscc::target::Target;
+use jscc::tsconfig::TsConfig;
 use jscc::CodeGenerator;
 use llvm_sys::{
     analysis::LLVMVerifyModule,
-    core::{LLVMBuildRetVoid, LLVMDisposeMessage, LLVMPrintModuleToString},
+    core::{LLVMDisposeMessage, LLVMPrintModuleToString},
     execution_engine::{
         LLVMCreateExecutionEngineForModule, LLVMExecutionEngineRef, LLVMLinkInInterpreter,
         LLVMRunFunction,
     },
 };
+use std::path::{Path, PathBuf};
+use tracing::info_span;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+mod cli;
+mod ice;
 
 fn main() -> Result<(), String> {
-    let js_code = "puts('Hello, World!\\n');";
-    let js_code_bytes = js_code.as_bytes();
+    let options = Options::parse(std::env::args().skip(1))?;
+
+    if let Some(filter) = &options.trace_codegen {
+        init_tracing(filter, options.trace_json);
+    }
+
+    ice::install_hook();
+    ice::catch(&options.input, || compile(&options))
+}
+
+fn init_tracing(filter: &str, json: bool) {
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(filter))
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+
+    if json {
+        subscriber.json().init();
+    } else {
+        subscriber.init();
+    }
+}
+
+fn compile(options: &Options) -> Result<(), String> {
+    let js_code_bytes = std::fs::read(&options.input)
+        .map_err(|err| format!("Failed to read {}: {}", options.input.display(), err))?;
     let mut parser = Parser::new(Source::from_bytes(&js_code_bytes));
     let mut codegen = CodeGenerator::default();
+    codegen.verify_each = options.verify_each;
+    codegen.unsupported = options.unsupported;
+    codegen.rust_bindings = options.emit.contains(&Emit::RustBindings);
+    codegen.opt_level = options.opt_level;
+    codegen.source_name = options.input.display().to_string();
+    // Without `main`, top-level code is exposed through a named init function
+    // declared in the generated header so the host program can call it. It is
+    // named up front so exports can't take its symbol.
+    if !options.has_main() {
+        codegen
+            .context
+            .set_root_function_name(&entry_point(&options.input));
+    }
+    if let Some(base_dir) = options.input.parent() {
+        codegen.base_dir = base_dir.to_path_buf();
+    }
+
+    let mut diagnostics =
+        DiagnosticEmitter::new(&options.input, &String::from_utf8_lossy(&js_code_bytes));
+    diagnostics.max_errors = options.max_errors;
+
+    let source = String::from_utf8_lossy(&js_code_bytes);
+    codegen.source_map = SourceMap::new(&source);
+    let pragmas = pragma::scan(&source)
+        .and_then(|pragmas| {
+            codegen.annotations = pragma::scan_annotations(&source)?;
+
+            Ok(pragmas)
+        })
+        .map_err(|diagnostic| {
+            diagnostics
+                .emit(&diagnostic)
+                .and_then(|()| diagnostics.finish())
+                .unwrap_err()
+        })?;
 
     let mut interner = Interner::new();
-    let ast = parser.parse_module(&mut interner).unwrap();
+    let tsconfig = if is_typescript(&options.input) {
+        TsConfig::find(&options.input)?
+    } else {
+        None
+    };
+    let input_kind = options
+        .input_kind
+        .resolve(&options.input, &js_code_bytes, tsconfig.as_ref());
+    // Annex B only applies to sloppy mode code, and modules are always strict.
+    codegen.annex_b = options.annex_b && input_kind != InputKind::Module;
+
+    match input_kind {
+        InputKind::Module => {
+            let ast = info_span!("parse", input = %options.input.display())
+                .in_scope(|| parser.parse_module(&mut interner))
+                .map_err(|err| report_parse_error(&mut diagnostics, &err, &options.input))?;
+            let statements = ast.items().items().iter().filter_map(|item| match item {
+                ModuleItem::StatementListItem(item) => Some(item),
+                _ => None,
+            });
+
+            check_early_errors(&mut diagnostics, statements.clone(), &interner, true)?;
+
+            info_span!("codegen", input = %options.input.display()).in_scope(|| {
+                codegen.declare_vars(statements.clone(), &interner);
+                codegen.declare_exported_functions(ast.items().items(), &interner);
+                codegen.declare_functions(statements.clone(), &interner);
 
-    let mut last_value = None;
+                for (index, module_item) in ast.items().items().iter().enumerate() {
+                    ice::set_current_item(index, module_item.to_interned_string(&interner));
+                    codegen.compile_module_item(module_item, &interner);
+                }
 
-    for module_item in ast.items().items() {
-        last_value = codegen.compile_module_item(module_item, &interner);
+                ice::clear_current_item();
+                codegen.compile_function_bodies(statements, &interner);
+                codegen.compile_exported_function_bodies(ast.items().items(), &interner);
+            });
+        }
+        InputKind::Script | InputKind::Auto => {
+            let ast = info_span!("parse", input = %options.input.display())
+                .in_scope(|| parser.parse_script(&mut interner))
+                .map_err(|err| report_parse_error(&mut diagnostics, &err, &options.input))?;
+            let strict = has_use_strict_directive(ast.statements(), &interner);
+            codegen.annex_b &= !strict;
+
+            check_early_errors(&mut diagnostics, ast.statements().iter(), &interner, strict)?;
+
+            info_span!("codegen", input = %options.input.display()).in_scope(|| {
+                codegen.declare_vars(ast.statements().iter(), &interner);
+                codegen.declare_functions(ast.statements().iter(), &interner);
+
+                for (index, item) in ast.statements().iter().enumerate() {
+                    ice::set_current_item(index, item.to_interned_string(&interner));
+                    codegen.compile_script_item(item, &interner);
+                }
+
+                ice::clear_current_item();
+                codegen.compile_function_bodies(ast.statements().iter(), &interner);
+            });
+        }
     }
+    ice::clear_current_item();
 
-    let last_value = last_value.unwrap();
+    for diagnostic in codegen.diagnostics.drain(..) {
+        diagnostics.emit(&diagnostic)?;
+    }
+    diagnostics.finish()?;
 
-    unsafe {
-        LLVMBuildRetVoid(codegen.context.builder);
+    codegen.finish_root_function()?;
+
+    match options.command {
+        Command::Run => run(codegen),
+        Command::Build => build(codegen, options, &pragmas, &mut diagnostics),
+    }
+}
+
+// Boa's parser already rejects most early errors. This catches the rest, and
+// the errors codegen can't see, before anything is compiled.
+fn check_early_errors<'a>(
+    diagnostics: &mut DiagnosticEmitter,
+    items: impl IntoIterator<Item = &'a StatementListItem> + Clone,
+    interner: &Interner,
+    strict: bool,
+) -> Result<(), String> {
+    let mut checker = EarlyErrors::new(interner, strict);
+    info_span!("check").in_scope(|| checker.check(items));
+
+    for diagnostic in &checker.diagnostics {
+        diagnostics.emit(diagnostic)?;
+    }
+
+    diagnostics.finish()
+}
+
+// Boa stops at the first syntax error, so a parse failure always ends
+// compilation after it has been reported.
+fn report_parse_error(
+    diagnostics: &mut DiagnosticEmitter,
+    error: &boa_parser::Error,
+    input: &Path,
+) -> String {
+    let mut diagnostic = Diagnostic::from_parse_error(error);
+    if is_typescript(input) {
+        diagnostic = diagnostic.with_note(
+            "TypeScript inputs are parsed as JavaScript; type annotations are not supported yet",
+        );
+    }
+
+    diagnostics
+        .emit(&diagnostic)
+        .and_then(|()| diagnostics.finish())
+        .unwrap_err()
+}
+
+// Where a build writes each artifact it produces.
+struct Artifacts {
+    out_dir: PathBuf,
+    output: PathBuf,
+    header: PathBuf,
+    bindings: PathBuf,
+    emits: Vec<(Emit, PathBuf)>,
+}
+
+fn artifacts(options: &Options) -> Artifacts {
+    let stem = options
+        .input
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    // Artifacts go into the current directory by default rather than next to
+    // the sources.
+    let out_dir = options
+        .out_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+    let output = options
+        .output
+        .clone()
+        .unwrap_or_else(|| match options.output_kind {
+            OutputKind::Executable => {
+                out_dir.join(format!("{}{}", stem, std::env::consts::EXE_SUFFIX))
+            }
+            OutputKind::StaticLib => out_dir.join(format!("lib{}.a", stem)),
+        });
+
+    let emits = [
+        (Emit::Object, "o"),
+        (Emit::LlvmIr, "ll"),
+        (Emit::Bitcode, "bc"),
+        (Emit::Assembly, "s"),
+    ]
+    .into_iter()
+    .filter(|(emit, _)| options.emit.contains(emit))
+    .map(|(emit, extension)| (emit, out_dir.join(format!("{}.{}", stem, extension))))
+    .collect();
+
+    Artifacts {
+        header: output.with_extension("h"),
+        bindings: output.with_extension("rs"),
+        out_dir,
+        output,
+        emits,
+    }
+}
+
+// Build output never replaces the source it is built from, however the paths
+// to them are spelled.
+fn refuse_to_overwrite<'a>(
+    input: &Path,
+    outputs: impl IntoIterator<Item = &'a PathBuf>,
+) -> Result<(), String> {
+    if outputs.into_iter().any(|path| is_same_file(path, input)) {
+        return Err(format!(
+            "Refusing to overwrite the input file {} with build output",
+            input.display()
+        ));
+    }
+
+    Ok(())
+}
+
+fn entry_point(input: &Path) -> String {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+
+    format!("{}_init", c_identifier(&stem))
+}
 
+fn run(codegen: CodeGenerator) -> Result<(), String> {
+    unsafe {
         let ir = LLVMPrintModuleToString(codegen.context.module);
         println!("{}", std::ffi::CStr::from_ptr(ir).to_string_lossy());
         LLVMDisposeMessage(ir);
@@ -55,3 +309,170 @@ fn main() -> Result<(), String> {
 
     Ok(())
 }
+
+fn build(
+    codegen: CodeGenerator,
+    options: &Options,
+    pragmas: &[Pragma],
+    diagnostics: &mut DiagnosticEmitter,
+) -> Result<(), String> {
+    let mut target = match &options.target {
+        Some(triple) => Target::new(triple),
+        None => Target::host(),
+    };
+    target.min_os_version = options.min_os_version.clone();
+    target.static_link |= options.static_link;
+    // Mobile platforms only load position independent code.
+    target.pic = !options.no_pic || target.is_android() || target.is_ios();
+
+    let Artifacts {
+        out_dir,
+        output,
+        header,
+        bindings,
+        emits,
+    } = artifacts(options);
+    refuse_to_overwrite(
+        &options.input,
+        [&output, &header, &bindings]
+            .into_iter()
+            .chain(emits.iter().map(|(_, path)| path)),
+    )?;
+
+    let entry_point = entry_point(&options.input);
+
+    for directory in [Some(out_dir.as_path()), output.parent()]
+        .into_iter()
+        .flatten()
+        .filter(|directory| !directory.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(directory)
+            .map_err(|err| format!("Failed to create {}: {}", directory.display(), err))?;
+    }
+
+    for (emit, path) in &emits {
+        let pending = PendingOutput::new(path);
+        let temp_path = pending.temp_path();
+
+        match emit {
+            Emit::Object => codegen.context.write_object_file(&target, temp_path)?,
+            Emit::LlvmIr => codegen.context.write_llvm_ir(&target, temp_path)?,
+            Emit::Bitcode => codegen.context.write_bitcode(&target, temp_path)?,
+            Emit::Assembly => codegen.context.write_assembly_file(&target, temp_path)?,
+            _ => unreachable!(),
+        }
+
+        pending.commit()?;
+    }
+
+    if !options.has_main() {
+        write_header(&header, &entry_point, &codegen.exports)?;
+    }
+
+    if options.emit.contains(&Emit::RustBindings) {
+        write_rust_bindings(&bindings, &entry_point, &codegen.exports)?;
+    }
+
+    if !options.should_link() {
+        return Ok(());
+    }
+
+    // The object file is only an intermediate, so it is never committed and
+    // gets removed once linking is done.
+    let object = PendingOutput::new(&PathBuf::from(format!("{}.o", output.display())));
+    codegen
+        .context
+        .write_object_file(&target, object.temp_path())?;
+
+    let mut link = options.link.clone();
+    let source_dir = options.input.parent().unwrap_or(Path::new("."));
+    for pragma in pragmas {
+        match pragma {
+            Pragma::Link(library) => link.libraries.push(library.clone()),
+            Pragma::LinkSearch(path) => link.search_paths.push(source_dir.join(path)),
+        }
+    }
+
+    let artifact = PendingOutput::new(&output);
+    let warnings = match options.output_kind {
+        OutputKind::Executable => link_executable(object.temp_path(), &artifact, &target, &link)?,
+        OutputKind::StaticLib => {
+            create_static_library(object.temp_path(), artifact.temp_path(), &target)?;
+
+            vec![]
+        }
+    };
+    artifact.commit()?;
+
+    for warning in &warnings {
+        diagnostics.emit(warning)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifacts_for(args: &str) -> Artifacts {
+        let options = Options::parse(args.split_whitespace().map(String::from)).unwrap();
+
+        artifacts(&options)
+    }
+
+    #[test]
+    fn names_artifacts_after_the_input() {
+        let artifacts = artifacts_for("build src/app.js");
+        let executable = format!("app{}", std::env::consts::EXE_SUFFIX);
+        assert_eq!(artifacts.output, Path::new(".").join(executable));
+        assert!(artifacts.emits.is_empty());
+
+        let artifacts = artifacts_for("build src/app.js --output-kind staticlib --out-dir out");
+        assert_eq!(artifacts.output, Path::new("out/libapp.a"));
+        assert_eq!(artifacts.header, Path::new("out/libapp.h"));
+
+        let artifacts =
+            artifacts_for("build src/app.js --emit obj,llvm-ir,llvm-bc,asm --out-dir out");
+        let emits: Vec<_> = artifacts
+            .emits
+            .iter()
+            .map(|(_, path)| path.as_path())
+            .collect();
+        assert_eq!(
+            emits,
+            ["out/app.o", "out/app.ll", "out/app.bc", "out/app.s"].map(Path::new)
+        );
+    }
+
+    #[test]
+    fn names_headers_and_bindings_after_the_output() {
+        let artifacts = artifacts_for(
+            "build src/app.js -o bin/app.a --output-kind staticlib --emit rust-bindings",
+        );
+
+        assert_eq!(artifacts.output, Path::new("bin/app.a"));
+        assert_eq!(artifacts.header, Path::new("bin/app.h"));
+        assert_eq!(artifacts.bindings, Path::new("bin/app.rs"));
+    }
+
+    #[test]
+    fn refuses_to_overwrite_the_input() {
+        let dir = std::env::temp_dir().join(format!("jscc-{}-input", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("main.js");
+        std::fs::write(&input, b"").unwrap();
+
+        let error =
+            refuse_to_overwrite(&input, [&dir.join("main"), &dir.join("./main.js")]).unwrap_err();
+        assert_eq!(
+            error,
+            format!(
+                "Refusing to overwrite the input file {} with build output",
+                input.display()
+            )
+        );
+        assert!(refuse_to_overwrite(&input, [&dir.join("main.o")]).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
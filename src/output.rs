@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+// An output file that is produced under a temporary name next to its final
+// location and only renamed into place once it's complete, so a failed or
+// interrupted build never leaves a truncated artifact behind.
+pub struct PendingOutput {
+    path: PathBuf,
+    temp_path: PathBuf,
+    committed: bool,
+}
+
+impl PendingOutput {
+    pub fn new(path: &Path) -> Self {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        // Keep the original extension so tools that dispatch on it, like the
+        // compiler driver used for linking, still recognise the file.
+        let temp_path = path.with_file_name(format!(".jscc-{}-{}", std::process::id(), file_name));
+
+        Self {
+            path: path.to_path_buf(),
+            temp_path,
+            committed: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn temp_path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    pub fn commit(mut self) -> Result<(), String> {
+        std::fs::rename(&self.temp_path, &self.path).map_err(|err| {
+            format!(
+                "Failed to move output into {}: {}",
+                self.path.display(),
+                err
+            )
+        })?;
+        self.committed = true;
+
+        Ok(())
+    }
+}
+
+impl Drop for PendingOutput {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let output = PendingOutput::new(path);
+
+    std::fs::write(output.temp_path(), contents)
+        .map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
+
+    output.commit()
+}
+
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A directory of its own for each test, since tests run in parallel.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jscc-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn entries(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect()
+    }
+
+    #[test]
+    fn commits_by_renaming() {
+        let dir = test_dir("commit");
+        let path = dir.join("main.o");
+        write_atomic(&path, b"object").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"object");
+        assert_eq!(entries(&dir), [path]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn removes_uncommitted_outputs() {
+        let dir = test_dir("uncommitted");
+        let output = PendingOutput::new(&dir.join("main"));
+        std::fs::write(output.temp_path(), b"partial").unwrap();
+        drop(output);
+
+        assert!(entries(&dir).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cleans_up_when_the_rename_fails() {
+        let dir = test_dir("rename");
+        // A file can't replace a directory that isn't empty.
+        let path = dir.join("main");
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("keep"), b"").unwrap();
+
+        let error = write_atomic(&path, b"executable").unwrap_err();
+        assert!(error.starts_with("Failed to move output into"), "{error}");
+        assert_eq!(entries(&dir), [path]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compares_paths_by_the_file_they_name() {
+        let dir = test_dir("same");
+        let input = dir.join("main.js");
+        std::fs::write(&input, b"").unwrap();
+
+        assert!(is_same_file(&input, &dir.join(".").join("main.js")));
+        assert!(!is_same_file(&input, &dir.join("main.o")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
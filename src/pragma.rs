@@ -0,0 +1,503 @@
+use crate::diagnostics::Diagnostic;
+use crate::header::c_identifier;
+use boa_ast::Position;
+use std::ops::Range;
+use std::path::PathBuf;
+
+// Build directives written in the source as `// @jscc <name>: <value>` line
+// comments, so a single file can carry everything needed to build it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pragma {
+    // `// @jscc link: sqlite3` links against a native library.
+    Link(String),
+    // `// @jscc link-search: vendor/lib` adds a library search path, relative
+    // to the source file.
+    LinkSearch(PathBuf),
+}
+
+// Values may list several libraries or paths separated by commas or
+// whitespace.
+pub fn scan(source: &str) -> Result<Vec<Pragma>, Diagnostic> {
+    let mut pragmas = vec![];
+
+    for comment in comments(source)
+        .into_iter()
+        .filter(|comment| !comment.block)
+    {
+        let Some(directive) = comment.text.trim_start().strip_prefix("@jscc") else {
+            continue;
+        };
+
+        let column = comment.text.find("@jscc").unwrap();
+        let position = position(source, comment.start + 2 + column);
+
+        let Some((name, value)) = directive.split_once(':') else {
+            return Err(
+                Diagnostic::error("Expected `// @jscc <name>: <value>`").with_position(position)
+            );
+        };
+        let values = value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|value| !value.is_empty());
+
+        match name.trim() {
+            "link" => pragmas.extend(values.map(|value| Pragma::Link(value.to_string()))),
+            "link-search" => {
+                pragmas.extend(values.map(|value| Pragma::LinkSearch(PathBuf::from(value))))
+            }
+            name => {
+                return Err(
+                    Diagnostic::error(format!("Unknown @jscc pragma `{}`", name))
+                        .with_position(position)
+                        .with_note("supported pragmas are `link` and `link-search`"),
+                )
+            }
+        }
+    }
+
+    Ok(pragmas)
+}
+
+// Attributes written as `/* @jscc <name> */` block comments right before a
+// function declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Annotation {
+    // `/* @jscc inline */` inlines the function into every caller, even when
+    // nothing else is optimized.
+    Inline,
+    // `/* @jscc export_name("c_name") */` gives an exported function a
+    // different symbol name.
+    ExportName(String),
+    // `/* @jscc hidden */` keeps an exported function out of the dynamic
+    // symbol table.
+    Hidden,
+}
+
+// Annotations are keyed by the position of the `function` keyword of the
+// declaration they apply to, since functions in different scopes can share a
+// name.
+pub fn scan_annotations(source: &str) -> Result<Vec<(Position, Annotation)>, Diagnostic> {
+    let mut annotations = vec![];
+
+    for comment in comments(source).into_iter().filter(|comment| comment.block) {
+        let Some(name) = comment.text.trim().strip_prefix("@jscc") else {
+            continue;
+        };
+
+        let position = position(source, comment.start);
+
+        let annotation = match name.trim() {
+            "inline" => Annotation::Inline,
+            "hidden" => Annotation::Hidden,
+            name if name.starts_with("export_name") => match export_name(name) {
+                Some(symbol) => Annotation::ExportName(symbol.to_string()),
+                None => {
+                    return Err(Diagnostic::error(
+                        "Expected `/* @jscc export_name(\"<symbol>\") */`",
+                    )
+                    .with_position(position)
+                    .with_note("the symbol must be a C identifier"))
+                }
+            },
+            name => {
+                return Err(
+                    Diagnostic::error(format!("Unknown @jscc annotation `{}`", name))
+                        .with_position(position)
+                        .with_note(
+                            "supported annotations are `inline`, `export_name` and `hidden`",
+                        ),
+                )
+            }
+        };
+
+        let Some((offset, function, exported)) = annotated_function(&source[comment.end..]) else {
+            return Err(Diagnostic::error(
+                "@jscc annotations must come right before a function declaration",
+            )
+            .with_position(position));
+        };
+
+        // Only exports have a symbol and visibility of their own.
+        if !exported && matches!(annotation, Annotation::ExportName(_) | Annotation::Hidden) {
+            return Err(Diagnostic::error(format!(
+                "`@jscc {}` only applies to exported functions",
+                name.trim()
+            ))
+            .with_position(position)
+            .with_note(format!(
+                "export the function with `export function {}`",
+                function
+            )));
+        }
+
+        annotations.push((position(source, comment.end + offset), annotation));
+    }
+
+    Ok(annotations)
+}
+
+// The symbol in `export_name("<symbol>")`, which must be a valid C identifier
+// to be declared in the header.
+fn export_name(annotation: &str) -> Option<&str> {
+    annotation
+        .strip_prefix("export_name")?
+        .trim_start()
+        .strip_prefix("(\"")?
+        .strip_suffix("\")")
+        .filter(|symbol| !symbol.is_empty() && c_identifier(symbol) == *symbol)
+}
+
+// The offset of the `function` keyword and the name of the function declared
+// at the start of `source`, after any further annotations and an `export`
+// keyword, and whether it is exported.
+fn annotated_function(input: &str) -> Option<(usize, &str, bool)> {
+    let mut source = input;
+    loop {
+        source = source.trim_start();
+
+        match source.strip_prefix("/*") {
+            Some(comment) => source = &comment[comment.find("*/")? + 2..],
+            None => break,
+        }
+    }
+
+    let exported = match source
+        .strip_prefix("export")
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+    {
+        Some(rest) => {
+            source = rest.trim_start();
+            true
+        }
+        None => false,
+    };
+
+    let offset = input.len() - source.len();
+    let source = source
+        .strip_prefix("function")
+        .filter(|rest| rest.starts_with(char::is_whitespace))?
+        .trim_start();
+    let end = source
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(source.len());
+
+    (end > 0).then(|| (offset, &source[..end], exported))
+}
+
+// A comment outside of any string literal. `start` is the offset of its `//`
+// or `/*`, and `end` is just past it.
+struct Comment<'a> {
+    start: usize,
+    end: usize,
+    text: &'a str,
+    block: bool,
+}
+
+// Source that isn't code: a comment, or a string or template literal
+// including its quotes.
+enum Lexeme<'a> {
+    Comment(Comment<'a>),
+    Literal(Range<usize>),
+}
+
+// Finds comments and literals the way the lexer would, in source order, so
+// `@jscc` inside a string is left alone. Whether a `/` starts a regular
+// expression literal is guessed from the code before it.
+fn lex(source: &str) -> Vec<Lexeme<'_>> {
+    let mut lexemes = vec![];
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '"' | '\'' | '`' => {
+                let mut end = source.len();
+
+                while let Some((offset, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        // Only template literals span lines.
+                        '\n' if c != '`' => {
+                            end = offset;
+                            break;
+                        }
+                        next if next == c => {
+                            end = offset + 1;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+
+                lexemes.push(Lexeme::Literal(start..end));
+            }
+            '/' if chars.next_if(|(_, next)| *next == '/').is_some() => {
+                let end = source[start..]
+                    .find('\n')
+                    .map_or(source.len(), |newline| start + newline);
+
+                lexemes.push(Lexeme::Comment(Comment {
+                    start,
+                    end,
+                    text: &source[start + 2..end],
+                    block: false,
+                }));
+                while chars.next_if(|(offset, _)| *offset < end).is_some() {}
+            }
+            '/' if chars.next_if(|(_, next)| *next == '*').is_some() => {
+                let Some(close) = source[start + 2..].find("*/") else {
+                    break;
+                };
+                let end = start + 2 + close + 2;
+
+                lexemes.push(Lexeme::Comment(Comment {
+                    start,
+                    end,
+                    text: &source[start + 2..end - 2],
+                    block: true,
+                }));
+                while chars.next_if(|(offset, _)| *offset < end).is_some() {}
+            }
+            '/' if starts_regex(&source[..start]) => {
+                let mut end = source.len();
+                let mut in_class = false;
+
+                while let Some((offset, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '\n' => {
+                            end = offset;
+                            break;
+                        }
+                        '[' => in_class = true,
+                        ']' => in_class = false,
+                        '/' if !in_class => {
+                            end = offset + 1;
+                            // Flags are part of the literal.
+                            while let Some((offset, _)) =
+                                chars.next_if(|(_, next)| next.is_alphanumeric())
+                            {
+                                end = offset + 1;
+                            }
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+
+                lexemes.push(Lexeme::Literal(start..end));
+            }
+            _ => {}
+        }
+    }
+
+    lexemes
+}
+
+// Keywords after which an expression, and so a regular expression, can start.
+const KEYWORDS_BEFORE_EXPRESSIONS: &[&str] = &[
+    "await",
+    "case",
+    "delete",
+    "do",
+    "else",
+    "in",
+    "instanceof",
+    "new",
+    "of",
+    "return",
+    "throw",
+    "typeof",
+    "void",
+    "yield",
+];
+
+// A `/` after an operand, like a name, a number or a closing bracket, is
+// division. Anywhere else it starts a regular expression.
+fn starts_regex(before: &str) -> bool {
+    let before = before.trim_end();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+
+    match before.chars().next_back() {
+        None => true,
+        Some(')' | ']' | '}' | '"' | '\'' | '`') => false,
+        Some(c) if is_word(c) => {
+            let word = before.rsplit(|c| !is_word(c)).next().unwrap_or_default();
+
+            KEYWORDS_BEFORE_EXPRESSIONS.contains(&word)
+        }
+        Some(_) => true,
+    }
+}
+
+fn comments(source: &str) -> Vec<Comment<'_>> {
+    lex(source)
+        .into_iter()
+        .filter_map(|lexeme| match lexeme {
+            Lexeme::Comment(comment) => Some(comment),
+            Lexeme::Literal(_) => None,
+        })
+        .collect()
+}
+
+// Blanks out comments and literals but keeps line breaks, so line-based
+// heuristics only see code.
+pub fn mask_comments_and_literals(source: &str) -> String {
+    let mut masked = String::with_capacity(source.len());
+    let mut offset = 0;
+
+    for range in lex(source).into_iter().map(|lexeme| match lexeme {
+        Lexeme::Comment(comment) => comment.start..comment.end,
+        Lexeme::Literal(range) => range,
+    }) {
+        masked.push_str(&source[offset..range.start]);
+        masked.extend(
+            source[range.clone()]
+                .chars()
+                .map(|c| if c == '\n' { c } else { ' ' }),
+        );
+        offset = range.end;
+    }
+    masked.push_str(&source[offset..]);
+
+    masked
+}
+
+fn position(source: &str, offset: usize) -> Position {
+    let line_start = source[..offset]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    let line = source[..offset].matches('\n').count() + 1;
+    // Columns count characters, like those of positions in the source map.
+    let column = source[line_start..offset].chars().count() + 1;
+
+    Position::new(line as u32, column as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_multiple_values() {
+        let source = "// @jscc link: sqlite3, z m\n// @jscc link-search: vendor/lib\n";
+
+        assert_eq!(
+            scan(source).unwrap(),
+            vec![
+                Pragma::Link("sqlite3".to_string()),
+                Pragma::Link("z".to_string()),
+                Pragma::Link("m".to_string()),
+                Pragma::LinkSearch(PathBuf::from("vendor/lib")),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_pragmas() {
+        let error = scan("let x = 1;\n  // @jscc link sqlite3\n").unwrap_err();
+        assert_eq!(error.position, Some(Position::new(2, 6)));
+
+        let error = scan("// @jscc linker: sqlite3\n").unwrap_err();
+        assert_eq!(error.message, "Unknown @jscc pragma `linker`");
+    }
+
+    #[test]
+    fn ignores_pragmas_in_strings() {
+        let source = "let a = \"// @jscc link: a\";\nlet b = `\n// @jscc link: b\n`;\n";
+
+        assert_eq!(scan(source).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn masks_regular_expressions_but_not_division() {
+        let source = "let re = /[/`]\\//g, half = a / 2;\nexport d\n";
+        let masked = mask_comments_and_literals(source);
+
+        assert_eq!(
+            masked.lines().collect::<Vec<_>>(),
+            ["let re =          , half = a / 2;", "export d"]
+        );
+    }
+
+    #[test]
+    fn masks_comments_and_literals() {
+        let source = "let a = 'x'; // export b\n/* import\nc */ `\nexport d\n`;\n";
+        let masked = mask_comments_and_literals(source);
+
+        assert_eq!(
+            masked.lines().map(str::trim_end).collect::<Vec<_>>(),
+            ["let a =    ;", "", "", "", " ;"]
+        );
+    }
+
+    #[test]
+    fn parses_export_name() {
+        let source = "/* @jscc export_name(\"c_add\") */\nexport function add(a, b) {}\n";
+
+        assert_eq!(
+            scan_annotations(source).unwrap(),
+            vec![(
+                Position::new(2, 8),
+                Annotation::ExportName("c_add".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_export_names() {
+        for annotation in [
+            "export_name(c_add)",
+            "export_name(\"\")",
+            "export_name(\"c-add\")",
+        ] {
+            let source = format!("/* @jscc {} */ function add() {{}}", annotation);
+
+            assert!(scan_annotations(&source).is_err(), "{}", annotation);
+        }
+    }
+
+    #[test]
+    fn collects_stacked_annotations() {
+        let source = "/* @jscc inline */ /* @jscc hidden */ export function f() {}";
+
+        assert_eq!(
+            scan_annotations(source).unwrap(),
+            vec![
+                (Position::new(1, 46), Annotation::Inline),
+                (Position::new(1, 46), Annotation::Hidden),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_export_annotations_on_local_functions() {
+        for annotation in ["hidden", "export_name(\"c_helper\")"] {
+            let source = format!("/* @jscc {} */\nfunction helper() {{}}\n", annotation);
+            let error = scan_annotations(&source).unwrap_err();
+
+            assert_eq!(
+                error.message,
+                format!("`@jscc {}` only applies to exported functions", annotation)
+            );
+            assert_eq!(error.position, Some(Position::new(1, 1)));
+        }
+    }
+
+    #[test]
+    fn requires_an_annotated_function() {
+        let error = scan_annotations("/* @jscc inline */\nconst f = 1;\n").unwrap_err();
+
+        assert_eq!(error.position, Some(Position::new(1, 1)));
+    }
+
+    #[test]
+    fn ignores_annotations_in_strings() {
+        let source = "const s = '/* @jscc inline */';\nfunction f() {}\n";
+
+        assert_eq!(scan_annotations(source).unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,216 @@
+use boa_interner::Sym;
+use llvm_sys::prelude::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    Var,
+    Let,
+    Const,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Local {
+    pub slot: LLVMValueRef,
+    pub ty: LLVMTypeRef,
+    pub kind: BindingKind,
+}
+
+#[derive(Default)]
+struct Scope {
+    bindings: HashMap<Sym, Local>,
+    // Function declarations, which compile to LLVM functions rather than
+    // slots.
+    functions: HashMap<Sym, LLVMValueRef>,
+    // `var` declarations are hoisted to the nearest function scope, while
+    // `let` and `const` stay in the block that declares them.
+    function: bool,
+}
+
+pub struct ScopeStack {
+    scopes: Vec<Scope>,
+}
+
+impl Default for ScopeStack {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope {
+                function: true,
+                ..Scope::default()
+            }],
+        }
+    }
+}
+
+impl ScopeStack {
+    pub fn push_block(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    pub fn push_function(&mut self) {
+        self.scopes.push(Scope {
+            function: true,
+            ..Scope::default()
+        });
+    }
+
+    pub fn pop(&mut self) {
+        assert!(self.scopes.len() > 1, "Popped the outermost scope");
+
+        self.scopes.pop();
+    }
+
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    // Only the current function's own bindings are visible; locals of an
+    // enclosing function live in another stack frame.
+    pub fn lookup(&self, name: Sym) -> Option<&Local> {
+        self.scopes[self.function_scope()..]
+            .iter()
+            .rev()
+            .find_map(|scope| scope.bindings.get(&name))
+    }
+
+    // Finds a local bound in an enclosing function, along with how many
+    // functions deep that function is. Nested functions reach it through the
+    // enclosing function's environment.
+    pub fn lookup_captured(&self, name: Sym) -> Option<(Local, usize)> {
+        if self.lookup(name).is_some() {
+            return None;
+        }
+
+        let index = self.scopes[..self.function_scope()]
+            .iter()
+            .rposition(|scope| scope.bindings.contains_key(&name))?;
+
+        Some((
+            self.scopes[index].bindings[&name],
+            self.function_depth(index),
+        ))
+    }
+
+    // How many functions enclose the scope at `index`, with top-level code at
+    // depth zero.
+    fn function_depth(&self, index: usize) -> usize {
+        self.scopes[..=index]
+            .iter()
+            .filter(|scope| scope.function)
+            .count()
+            - 1
+    }
+
+    fn function_scope(&self) -> usize {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.function)
+            .unwrap_or_default()
+    }
+
+    // The scopes a `var` is visible in, from the innermost block out to the
+    // function scope it belongs to.
+    fn var_scopes(&self) -> impl Iterator<Item = &Scope> {
+        self.scopes[self.function_scope()..].iter().rev()
+    }
+
+    pub fn lookup_var(&self, name: Sym) -> Option<&Local> {
+        self.var_scopes()
+            .find_map(|scope| scope.bindings.get(&name))
+            .filter(|local| local.kind == BindingKind::Var)
+    }
+
+    // Returns false if the name was already declared in the innermost scope.
+    pub fn declare_lexical(&mut self, name: Sym, local: Local) -> bool {
+        let scope = self.scopes.last_mut().unwrap();
+
+        if scope.bindings.contains_key(&name) {
+            return false;
+        }
+
+        scope.bindings.insert(name, local);
+
+        true
+    }
+
+    // Declares (or redeclares) a `var`, which may not share its name with a
+    // `let` or `const` in any block between here and the function scope.
+    // Returns false on such a conflict.
+    pub fn declare_var(&mut self, name: Sym, local: Local) -> bool {
+        let conflicts = self.var_scopes().any(|scope| {
+            scope
+                .bindings
+                .get(&name)
+                .is_some_and(|existing| existing.kind != BindingKind::Var)
+        });
+
+        if conflicts {
+            return false;
+        }
+
+        let function = self.function_scope();
+        self.scopes[function].bindings.insert(name, local);
+
+        true
+    }
+
+    // Points every binding that uses `slot` at `replacement` instead.
+    pub fn replace_slot(&mut self, slot: LLVMValueRef, replacement: LLVMValueRef) {
+        for local in self
+            .scopes
+            .iter_mut()
+            .flat_map(|scope| scope.bindings.values_mut())
+            .filter(|local| local.slot == slot)
+        {
+            local.slot = replacement;
+        }
+    }
+
+    // Functions are visible through function boundaries, unless a local of the
+    // same name is declared in a nearer scope or alongside them. Along with
+    // the function this returns the depth of the function that declared it,
+    // whose environment the callee expects.
+    pub fn lookup_function(&self, name: Sym) -> Option<(LLVMValueRef, usize)> {
+        for (index, scope) in self.scopes.iter().enumerate().rev() {
+            if scope.bindings.contains_key(&name) {
+                return None;
+            }
+
+            if let Some(function) = scope.functions.get(&name) {
+                return Some((*function, self.function_depth(index)));
+            }
+        }
+
+        None
+    }
+
+    // The function a declaration in the innermost scope was hoisted to.
+    pub fn hoisted_function(&self, name: Sym) -> Option<LLVMValueRef> {
+        self.scopes.last().unwrap().functions.get(&name).copied()
+    }
+
+    pub fn declare_function(&mut self, name: Sym, function: LLVMValueRef) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .functions
+            .insert(name, function);
+    }
+
+    pub fn in_function_scope(&self) -> bool {
+        self.scopes.last().unwrap().function
+    }
+
+    // Annex B makes a function declared in a block visible in the enclosing
+    // function as well, unless that would clash with a `let` or `const`.
+    pub fn declare_annex_b_function(&mut self, name: Sym, function: LLVMValueRef) {
+        let conflicts = self
+            .var_scopes()
+            .any(|scope| scope.bindings.contains_key(&name));
+
+        if !conflicts {
+            let scope = self.function_scope();
+            self.scopes[scope].functions.insert(name, function);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use boa_ast::function::FunctionDeclaration;
+use boa_ast::{Position, Statement};
+use boa_interner::{Interner, ToInternedString};
+
+// Boa's AST doesn't record source positions, so codegen finds a construct by
+// searching the source for the start of its printed form. Whitespace,
+// semicolons and quote styles are ignored, since the printer normalizes them.
+#[derive(Default)]
+pub struct SourceMap {
+    source: String,
+    // The significant characters of the source with their byte offsets.
+    compact: Vec<(usize, char)>,
+    // Where the last construct was found. Code is compiled mostly in source
+    // order, so searching from here first picks the right one of several
+    // identical constructs.
+    cursor: Option<usize>,
+}
+
+// Matching stops after this many characters, before the printed form is
+// likely to drift from the source.
+const MAX_NEEDLE_LENGTH: usize = 32;
+
+impl SourceMap {
+    pub fn new(source: &str) -> Self {
+        let compact = source
+            .char_indices()
+            .filter_map(|(offset, c)| significant(c).map(|c| (offset, c)))
+            .collect();
+
+        Self {
+            source: source.to_string(),
+            compact,
+            cursor: None,
+        }
+    }
+
+    // Finds the first line of `printed` in the source, which may be part of
+    // the construct found last.
+    pub fn locate(&mut self, printed: &str) -> Option<Position> {
+        self.locate_from(printed, self.cursor.unwrap_or(0))
+    }
+
+    // Finds the first line of `printed` in the source after the start of the
+    // construct found last, so a statement that repeats the previous one is
+    // found at its own position.
+    pub fn locate_next(&mut self, printed: &str) -> Option<Position> {
+        self.locate_from(printed, self.cursor.map_or(0, |cursor| cursor + 1))
+    }
+
+    // Like `locate`, but leaves the cursor alone, for declarations that are
+    // looked at out of source order when they are hoisted.
+    pub fn find(&self, printed: &str) -> Option<Position> {
+        let start = self.search(printed, self.cursor.unwrap_or(0))?;
+
+        Some(self.position(self.compact[start].0))
+    }
+
+    fn locate_from(&mut self, printed: &str, from: usize) -> Option<Position> {
+        let start = self.search(printed, from)?;
+
+        self.cursor = Some(start);
+
+        Some(self.position(self.compact[start].0))
+    }
+
+    // The index in `compact` where the first line of `printed` starts, looking
+    // from `from` onwards and then wrapping around.
+    fn search(&self, printed: &str, from: usize) -> Option<usize> {
+        let needle: Vec<char> = printed
+            .lines()
+            .next()?
+            .trim_end_matches('{')
+            .chars()
+            .filter_map(significant)
+            .take(MAX_NEEDLE_LENGTH)
+            .collect();
+
+        if needle.is_empty() {
+            return None;
+        }
+
+        let matches = |start: &usize| {
+            self.compact[*start..]
+                .iter()
+                .map(|(_, c)| *c)
+                .take(needle.len())
+                .eq(needle.iter().copied())
+        };
+        let from = from.min(self.compact.len());
+
+        (from..self.compact.len())
+            .find(matches)
+            .or_else(|| (0..from).find(matches))
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let before = &self.source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+
+        Position::new(line as u32, column as u32)
+    }
+}
+
+// The start of a statement's printed form. Compound statements stop before
+// their bodies, so locating each nested statement doesn't print its whole
+// subtree again.
+pub fn statement_head(statement: &Statement, interner: &Interner) -> String {
+    match statement {
+        Statement::Block(_) => "{".to_string(),
+        Statement::If(if_statement) => {
+            format!("if ({})", if_statement.cond().to_interned_string(interner))
+        }
+        Statement::WhileLoop(while_loop) => {
+            format!(
+                "while ({})",
+                while_loop.condition().to_interned_string(interner)
+            )
+        }
+        Statement::DoWhileLoop(_) => "do".to_string(),
+        Statement::ForLoop(_) | Statement::ForInLoop(_) | Statement::ForOfLoop(_) => {
+            "for (".to_string()
+        }
+        Statement::Switch(switch) => {
+            format!("switch ({})", switch.val().to_interned_string(interner))
+        }
+        Statement::Labelled(labelled) => {
+            format!("{}:", interner.resolve_expect(labelled.label()))
+        }
+        Statement::Try(_) => "try".to_string(),
+        statement => statement.to_interned_string(interner),
+    }
+}
+
+pub fn function_head(function: &FunctionDeclaration, interner: &Interner) -> String {
+    format!(
+        "function {}(",
+        interner.resolve_expect(function.name().sym())
+    )
+}
+
+fn significant(c: char) -> Option<char> {
+    match c {
+        _ if c.is_whitespace() => None,
+        ';' => None,
+        '\'' | '`' => Some('"'),
+        c => Some(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_repeated_statements_in_order() {
+        let mut source_map = SourceMap::new("f();\nf();\n");
+
+        assert_eq!(source_map.locate_next("f();"), Some(Position::new(1, 1)));
+        assert_eq!(source_map.locate("f()"), Some(Position::new(1, 1)));
+        assert_eq!(source_map.locate_next("f();"), Some(Position::new(2, 1)));
+        // Code compiled out of order is found by wrapping around.
+        assert_eq!(source_map.locate_next("f();"), Some(Position::new(1, 1)));
+    }
+
+    #[test]
+    fn ignores_whitespace_semicolons_and_quote_styles() {
+        let mut source_map = SourceMap::new("let a = 1;\n  let  s = 'x'\n");
+
+        assert_eq!(
+            source_map.locate_next("let s = \"x\";"),
+            Some(Position::new(2, 3))
+        );
+        assert_eq!(source_map.locate_next("{"), None);
+    }
+}
@@ -0,0 +1,359 @@
+use llvm_sys::core::LLVMDisposeMessage;
+use llvm_sys::target_machine::LLVMGetDefaultTargetTriple;
+use std::ffi::{CStr, OsString};
+use std::path::PathBuf;
+use std::process::Command;
+
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub triple: String,
+    pub min_os_version: Option<String>,
+    pub static_link: bool,
+    pub pic: bool,
+    pub sysroot: Option<PathBuf>,
+}
+
+impl Target {
+    pub fn host() -> Self {
+        let triple = unsafe {
+            let triple = LLVMGetDefaultTargetTriple();
+            let string = CStr::from_ptr(triple).to_string_lossy().into_owned();
+            LLVMDisposeMessage(triple);
+            string
+        };
+
+        Self::new(&triple)
+    }
+
+    pub fn new(triple: &str) -> Self {
+        let mut target = Self {
+            triple: triple.to_string(),
+            min_os_version: None,
+            static_link: false,
+            pic: true,
+            sysroot: None,
+        };
+
+        // musl binaries are only worth producing when they don't depend on
+        // the host's libc at all.
+        if target.is_musl() {
+            target.static_link = true;
+        }
+
+        if target.is_android() {
+            target.sysroot = android_ndk().map(|ndk| ndk.join("sysroot"));
+        }
+
+        if target.is_ios() {
+            target.sysroot =
+                xcrun(&["--sdk", target.apple_sdk(), "--show-sdk-path"]).map(PathBuf::from);
+        }
+
+        target
+    }
+
+    pub fn arch(&self) -> &str {
+        self.triple.split('-').next().unwrap_or_default()
+    }
+
+    pub fn is_arm64(&self) -> bool {
+        matches!(self.arch(), "arm64" | "aarch64" | "arm64e")
+    }
+
+    // The last component of the triple, such as `gnu`, `musleabihf` or
+    // `android`.
+    pub fn environment(&self) -> &str {
+        self.triple.rsplit('-').next().unwrap_or_default()
+    }
+
+    // 32-bit ARM triples spell the environment `musleabi` or `musleabihf`.
+    pub fn is_musl(&self) -> bool {
+        self.environment().starts_with("musl")
+    }
+
+    pub fn is_android(&self) -> bool {
+        self.triple.contains("-android")
+    }
+
+    pub fn is_apple(&self) -> bool {
+        self.triple.contains("-apple-")
+    }
+
+    pub fn is_macos(&self) -> bool {
+        self.is_apple() && (self.triple.contains("-darwin") || self.triple.contains("-macos"))
+    }
+
+    pub fn is_ios(&self) -> bool {
+        self.is_apple() && self.triple.contains("-ios")
+    }
+
+    // There are no Intel iOS devices, so x86 iOS triples are simulators even
+    // without a suffix.
+    pub fn is_simulator(&self) -> bool {
+        self.triple.ends_with("-simulator")
+            || self.triple.ends_with("-sim")
+            || (self.is_ios() && matches!(self.arch(), "x86_64" | "i386"))
+    }
+
+    pub fn apple_sdk(&self) -> &'static str {
+        match (self.is_ios(), self.is_simulator()) {
+            (true, true) => "iphonesimulator",
+            (true, false) => "iphoneos",
+            _ => "macosx",
+        }
+    }
+
+    pub fn android_api_level(&self) -> String {
+        // API 21 is the oldest level the NDK still supports for 64-bit ABIs.
+        self.min_os_version
+            .clone()
+            .unwrap_or_else(|| "21".to_string())
+    }
+
+    pub fn ios_version_min(&self) -> String {
+        self.min_os_version
+            .clone()
+            .unwrap_or_else(|| "12.0".to_string())
+    }
+
+    pub fn linker(&self) -> Command {
+        if self.is_musl() {
+            if self.arch() == std::env::consts::ARCH {
+                return Command::new("musl-gcc");
+            }
+
+            return Command::new(format!("{}-linux-{}-gcc", self.arch(), self.environment()));
+        }
+
+        if self.is_android() {
+            let clang = android_ndk()
+                .map(|ndk| ndk.join("bin").join("clang"))
+                .unwrap_or_else(|| PathBuf::from("clang"));
+            let mut command = Command::new(clang);
+            command
+                .arg(format!("--target={}", self.clang_triple()))
+                .args(self.platform_args());
+
+            return command;
+        }
+
+        if self.is_ios() {
+            let mut command = Command::new("xcrun");
+            command
+                .args(["--sdk", self.apple_sdk(), "clang"])
+                .args(["-arch", self.apple_arch()])
+                .args(self.platform_args());
+
+            return command;
+        }
+
+        // Apple's cc links for the host's architecture unless told otherwise.
+        if self.is_macos() {
+            let mut command = Command::new("cc");
+            command.args(["-arch", self.apple_arch()]);
+
+            return command;
+        }
+
+        Command::new("cc")
+    }
+
+    // The architecture as Apple's `-arch` flag names it.
+    pub fn apple_arch(&self) -> &str {
+        if self.is_arm64() {
+            "arm64"
+        } else {
+            self.arch()
+        }
+    }
+
+    // Android triples carry the API level, which selects the NDK's libraries.
+    pub fn clang_triple(&self) -> String {
+        if self.is_android() {
+            return format!("{}{}", self.triple, self.android_api_level());
+        }
+
+        self.triple.clone()
+    }
+
+    // Flags any clang driver needs to link for the target's platform, whichever
+    // linker flavor is used.
+    pub fn platform_args(&self) -> Vec<OsString> {
+        let mut args = vec![];
+
+        if self.is_android() {
+            if let Some(sysroot) = &self.sysroot {
+                let mut arg = OsString::from("--sysroot=");
+                arg.push(sysroot);
+                args.push(arg);
+            }
+        }
+
+        if self.is_ios() {
+            let version_flag = if self.is_simulator() {
+                "-mios-simulator-version-min"
+            } else {
+                "-mios-version-min"
+            };
+            args.push(format!("{}={}", version_flag, self.ios_version_min()).into());
+
+            if let Some(sysroot) = &self.sysroot {
+                args.push("-isysroot".into());
+                args.push(sysroot.into());
+            }
+        }
+
+        args
+    }
+
+    pub fn macos_version_min(&self) -> String {
+        match &self.min_os_version {
+            Some(version) => version.clone(),
+            // Apple Silicon only exists from macOS 11 onwards.
+            None if self.is_arm64() => "11.0".to_string(),
+            None => "10.13".to_string(),
+        }
+    }
+
+    pub fn macos_sdk_version(&self) -> String {
+        xcrun(&["--sdk", "macosx", "--show-sdk-version"])
+            .unwrap_or_else(|| self.macos_version_min())
+    }
+}
+
+fn xcrun(args: &[&str]) -> Option<String> {
+    Command::new("xcrun")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|output| !output.is_empty())
+}
+
+fn android_ndk() -> Option<PathBuf> {
+    let ndk =
+        std::env::var_os("ANDROID_NDK_HOME").or_else(|| std::env::var_os("ANDROID_NDK_ROOT"))?;
+    let host = match std::env::consts::OS {
+        "macos" => "darwin-x86_64",
+        "windows" => "windows-x86_64",
+        _ => "linux-x86_64",
+    };
+
+    Some(
+        PathBuf::from(ndk)
+            .join("toolchains")
+            .join("llvm")
+            .join("prebuilt")
+            .join(host),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<&str> {
+        command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn recognizes_musl_environments() {
+        for triple in [
+            "x86_64-unknown-linux-musl",
+            "arm-unknown-linux-musleabi",
+            "armv7-unknown-linux-musleabihf",
+        ] {
+            let target = Target::new(triple);
+            assert!(target.is_musl(), "{triple}");
+            assert!(target.static_link, "{triple}");
+        }
+
+        assert!(!Target::new("x86_64-unknown-linux-gnu").is_musl());
+    }
+
+    #[test]
+    fn picks_linkers() {
+        let linker = Target::new("armv7-unknown-linux-musleabihf").linker();
+        assert_eq!(linker.get_program(), "armv7-linux-musleabihf-gcc");
+
+        let linker = Target::new("aarch64-apple-darwin").linker();
+        assert_eq!(linker.get_program(), "cc");
+        assert_eq!(args(&linker), ["-arch", "arm64"]);
+
+        let linker = Target::new("x86_64-unknown-linux-gnu").linker();
+        assert_eq!(linker.get_program(), "cc");
+        assert!(args(&linker).is_empty());
+    }
+
+    #[test]
+    fn treats_x86_ios_targets_as_simulators() {
+        for triple in [
+            "x86_64-apple-ios",
+            "i386-apple-ios",
+            "arm64-apple-ios-simulator",
+        ] {
+            let mut target = Target::new(triple);
+            target.sysroot = None;
+            assert!(target.is_simulator(), "{triple}");
+            assert_eq!(target.apple_sdk(), "iphonesimulator");
+            assert_eq!(target.platform_args(), ["-mios-simulator-version-min=12.0"]);
+        }
+
+        let target = Target::new("arm64-apple-ios");
+        assert!(!target.is_simulator());
+        assert_eq!(target.apple_sdk(), "iphoneos");
+
+        let mut target = Target::new("x86_64-apple-ios");
+        target.sysroot = None;
+        let linker = target.linker();
+        assert_eq!(linker.get_program(), "xcrun");
+        assert_eq!(
+            args(&linker),
+            [
+                "--sdk",
+                "iphonesimulator",
+                "clang",
+                "-arch",
+                "x86_64",
+                "-mios-simulator-version-min=12.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn adds_the_api_level_to_android_triples() {
+        let mut target = Target::new("aarch64-linux-android");
+        assert_eq!(target.clang_triple(), "aarch64-linux-android21");
+
+        target.min_os_version = Some("30".to_string());
+        assert_eq!(target.clang_triple(), "aarch64-linux-android30");
+
+        assert_eq!(
+            Target::new("x86_64-unknown-linux-gnu").clang_triple(),
+            "x86_64-unknown-linux-gnu"
+        );
+    }
+
+    #[test]
+    fn passes_platform_args() {
+        let mut target = Target::new("aarch64-linux-android");
+        target.sysroot = Some(PathBuf::from("/ndk/sysroot"));
+        assert_eq!(target.platform_args(), ["--sysroot=/ndk/sysroot"]);
+
+        let mut target = Target::new("arm64-apple-ios");
+        target.sysroot = Some(PathBuf::from("/sdk"));
+        target.min_os_version = Some("15.0".to_string());
+        assert_eq!(
+            target.platform_args(),
+            ["-mios-version-min=15.0", "-isysroot", "/sdk"]
+        );
+
+        assert!(Target::new("x86_64-unknown-linux-gnu")
+            .platform_args()
+            .is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+
+// The subset of `tsconfig.json` that affects how jscc treats TypeScript
+// inputs.
+#[derive(Debug, Default, Clone)]
+pub struct TsConfig {
+    pub path: PathBuf,
+    pub module: Option<String>,
+    pub target: Option<String>,
+}
+
+impl TsConfig {
+    // Looks for the nearest `tsconfig.json` in the input's directory or any of
+    // its ancestors, the same way `tsc` does.
+    pub fn find(input: &Path) -> Result<Option<Self>, String> {
+        // A relative path like `main.ts` has no ancestors beyond `.`, so the
+        // search starts from the absolute path.
+        let input = input.canonicalize().unwrap_or_else(|_| input.to_path_buf());
+        let start = input
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        for directory in start.ancestors() {
+            let path = directory.join("tsconfig.json");
+
+            if path.is_file() {
+                return Self::load(&path).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let json: serde_json::Value = serde_json::from_str(&strip_jsonc(&contents))
+            .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))?;
+
+        let compiler_option = |name: &str| {
+            json.get("compilerOptions")
+                .and_then(|options| options.get(name))
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_ascii_lowercase())
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            module: compiler_option("module"),
+            target: compiler_option("target"),
+        })
+    }
+
+    // Without a module setting, tsc compiles projects targeting ES3 or ES5 to
+    // CommonJS.
+    pub fn is_commonjs(&self) -> bool {
+        match self.module.as_deref() {
+            Some(module) => module == "commonjs",
+            None => matches!(self.target.as_deref(), Some("es3" | "es5")),
+        }
+    }
+}
+
+// `tsconfig.json` is JSONC, which allows comments and trailing commas. Both
+// are removed, leaving plain JSON with the same line numbers for errors.
+fn strip_jsonc(contents: &str) -> String {
+    let mut json = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    // A comma is only written once the next token shows it isn't trailing.
+    let mut pending_comma = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|c| *c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+
+                let mut previous = None;
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        json.push(c);
+                    }
+                    if previous == Some('*') && c == '/' {
+                        break;
+                    }
+                    previous = Some(c);
+                }
+            }
+            c if c.is_whitespace() => json.push(c),
+            ',' => pending_comma = true,
+            c => {
+                if std::mem::take(&mut pending_comma) && c != '}' && c != ']' {
+                    json.push(',');
+                }
+                json.push(c);
+
+                if c == '"' {
+                    while let Some(c) = chars.next() {
+                        json.push(c);
+
+                        match c {
+                            '\\' => json.extend(chars.next()),
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> serde_json::Value {
+        serde_json::from_str(&strip_jsonc(contents)).unwrap()
+    }
+
+    #[test]
+    fn strips_trailing_commas() {
+        let json = parse("{\"compilerOptions\": {\"lib\": [\"es2020\", \"dom\",],},}");
+
+        assert_eq!(
+            json,
+            serde_json::json!({ "compilerOptions": { "lib": ["es2020", "dom"] } })
+        );
+    }
+
+    #[test]
+    fn strips_comments_outside_strings() {
+        let json = parse(
+            "{\n  // line comment\n  \"a\": \"// kept\", /* block */\n  \"b\": \"/* kept */\",\n}",
+        );
+
+        assert_eq!(
+            json,
+            serde_json::json!({ "a": "// kept", "b": "/* kept */" })
+        );
+    }
+
+    #[test]
+    fn strips_block_comments_ending_in_stars() {
+        let json = parse("{\n/**\n * docs\n **/\n\"a\": 1 /***/, \"b\": \"\\\"\"}");
+
+        assert_eq!(json, serde_json::json!({ "a": 1, "b": "\"" }));
+    }
+
+    #[test]
+    fn keeps_line_numbers() {
+        let contents = "{\n/* one\ntwo */\n\"a\": 1, // three\n}";
+
+        assert_eq!(
+            strip_jsonc(contents).lines().count(),
+            contents.lines().count()
+        );
+    }
+
+    #[test]
+    fn defaults_to_commonjs_for_es5_targets() {
+        let config = |module: Option<&str>, target: Option<&str>| TsConfig {
+            module: module.map(str::to_string),
+            target: target.map(str::to_string),
+            ..TsConfig::default()
+        };
+
+        assert!(config(Some("commonjs"), None).is_commonjs());
+        assert!(config(None, Some("es5")).is_commonjs());
+        assert!(!config(None, Some("es2020")).is_commonjs());
+        assert!(!config(Some("esnext"), Some("es5")).is_commonjs());
+        assert!(!config(None, None).is_commonjs());
+    }
+}